@@ -0,0 +1,189 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The base of each limb of a BigUint
+const BASE: u64 = 1_000_000_000;
+
+/// An arbitrary precision unsigned integer, supporting just enough arithmetic for
+/// puzzles whose numbers outgrow the built-in types
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    /// Base 10^9 limbs, least significant first, with no leading zero limbs (so
+    /// zero has none)
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        Self { limbs: Vec::new() }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    /// Remove leading zero limbs
+    fn normalize(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    /// Convert to a u64, if the value fits
+    pub fn to_u64(&self) -> Option<u64> {
+        let mut value: u64 = 0;
+        for limb in self.limbs.iter().rev() {
+            value = value.checked_mul(BASE)?.checked_add(*limb as u64)?;
+        }
+        return Some(value);
+    }
+
+    pub fn add(&self, other: &BigUint) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64 + *other.limbs.get(i).unwrap_or(&0) as u64 + carry;
+            limbs.push((sum % BASE) as u32);
+            carry = sum / BASE;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+
+        return Self { limbs };
+    }
+
+    /// Subtract, returning None if the result would be negative
+    pub fn checked_sub(&self, other: &BigUint) -> Option<BigUint> {
+        if *self < *other {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0;
+        for i in 0..self.limbs.len() {
+            let mut diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if diff < 0 {
+                diff += BASE as i64;
+                borrow = 1;
+            }
+            limbs.push(diff as u32);
+        }
+
+        return Some(Self { limbs }.normalize());
+    }
+
+    pub fn mul(&self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return Self::zero();
+        }
+
+        let mut acc = vec![0u64; self.limbs.len() + other.limbs.len()];
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0;
+            for (j, b) in other.limbs.iter().enumerate() {
+                let t = acc[i + j] + *a as u64 * *b as u64 + carry;
+                acc[i + j] = t % BASE;
+                carry = t / BASE;
+            }
+            acc[i + other.limbs.len()] += carry;
+        }
+
+        return Self { limbs: acc.into_iter().map(|l| l as u32).collect() }.normalize();
+    }
+
+    /// Divide by a (non-zero) u64, returning the quotient and remainder
+    pub fn div_rem_u64(&self, divisor: u64) -> (BigUint, u64) {
+        assert!(divisor != 0, "division by zero");
+
+        let mut limbs = vec![0u32; self.limbs.len()];
+        let mut rem: u128 = 0;
+        for i in (0..self.limbs.len()).rev() {
+            let cur = rem * BASE as u128 + self.limbs[i] as u128;
+            limbs[i] = (cur / divisor as u128) as u32;
+            rem = cur % divisor as u128;
+        }
+
+        return (Self { limbs }.normalize(), rem as u64);
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(mut value: u64) -> Self {
+        let mut limbs = Vec::new();
+        while value > 0 {
+            limbs.push((value % BASE) as u32);
+            value /= BASE;
+        }
+        Self { limbs }
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.limbs.last() {
+            None => write!(f, "0"),
+            Some(top) => {
+                write!(f, "{top}")?;
+                for limb in self.limbs.iter().rev().skip(1) {
+                    write!(f, "{limb:09}")?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = BigUint::from(u64::MAX);
+        let b = BigUint::from(12345);
+
+        assert_eq!("18446744073709551615", a.to_string());
+        assert_eq!("18446744073709563960", a.add(&b).to_string());
+        assert_eq!("227725055589944414687175", a.mul(&b).to_string());
+        assert_eq!(Some(u64::MAX - 12345), a.checked_sub(&b).unwrap().to_u64());
+        assert_eq!(None, b.checked_sub(&a));
+        assert_eq!(BigUint::zero(), a.checked_sub(&a).unwrap());
+        assert_eq!(None, a.add(&b).to_u64());
+        assert_eq!(Some(u64::MAX), a.to_u64());
+
+        let square = a.mul(&a);
+        assert_eq!("340282366920938463426481119284349108225", square.to_string());
+        assert_eq!((a.clone(), 0), square.div_rem_u64(u64::MAX));
+        assert_eq!((BigUint::from(u64::MAX / 10), 5), a.div_rem_u64(10));
+
+        assert_eq!("0", BigUint::zero().to_string());
+        assert_eq!("1000000000", BigUint::from(1_000_000_000).to_string());
+        assert!(BigUint::zero().mul(&a).is_zero());
+    }
+
+    #[test]
+    fn ordering() {
+        let values = [0u64, 1, 999_999_999, 1_000_000_000, 1_000_000_001, u64::MAX];
+        for a in values {
+            for b in values {
+                assert_eq!(a.cmp(&b), BigUint::from(a).cmp(&BigUint::from(b)), "{a} {b}");
+            }
+        }
+    }
+}
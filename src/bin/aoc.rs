@@ -0,0 +1,233 @@
+#![allow(clippy::needless_return)]
+
+use advent_of_code_2022::solution::{self, Solution};
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "\
+Usage: aoc --day N [--part 1|2] [INPUT]
+       aoc --all [--data DIR]
+
+Options:
+  -d, --day N      Solve the puzzle for day N
+  -p, --part P     Solve only part P (both parts are solved by default)
+  -i, --input PATH Read the puzzle input from PATH ('-' or omitted for stdin)
+  -a, --all        Solve every day, reading DIR/dayN_actual.txt, with timings
+      --data DIR   Directory containing the puzzle inputs (default: data)
+  -h, --help       Show this message";
+
+/// Options read from the command line
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    day: Option<u32>,
+    part: Option<u32>,
+    input: Option<PathBuf>,
+    all: bool,
+    data_dir: Option<PathBuf>,
+    help: bool,
+}
+
+/// Parse command line arguments (excluding the program name)
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        let mut value = |name: &str| {
+            iter.next().cloned().ok_or_else(|| format!("{name} requires a value"))
+        };
+
+        match arg.as_str() {
+            "-d" | "--day" => {
+                let v = value(arg)?;
+                options.day = Some(v.parse::<u32>().map_err(|_| format!("invalid day: {v}"))?);
+            },
+            "-p" | "--part" => {
+                let v = value(arg)?;
+                match v.as_str() {
+                    "1" => options.part = Some(1),
+                    "2" => options.part = Some(2),
+                    _ => return Err(format!("invalid part: {v}")),
+                }
+            },
+            "-i" | "--input" => options.input = Some(PathBuf::from(value(arg)?)),
+            "-a" | "--all" => options.all = true,
+            "--data" => options.data_dir = Some(PathBuf::from(value(arg)?)),
+            "-h" | "--help" => options.help = true,
+            _ if arg.starts_with('-') && arg != "-" => return Err(format!("unknown option: {arg}")),
+            _ => {
+                if options.input.is_some() {
+                    return Err(format!("unexpected argument: {arg}"));
+                }
+                options.input = Some(PathBuf::from(arg));
+            },
+        }
+    }
+
+    if options.help {
+        return Ok(options);
+    }
+
+    if options.all {
+        if options.day.is_some() || options.part.is_some() || options.input.is_some() {
+            return Err("--all can't be combined with --day, --part or an input".to_string());
+        }
+    } else {
+        if options.day.is_none() {
+            return Err("either --day or --all is required".to_string());
+        }
+        if options.data_dir.is_some() {
+            return Err("--data is only used with --all".to_string());
+        }
+    }
+
+    return Ok(options);
+}
+
+/// Open the puzzle input, using stdin if no path (or '-') is given
+fn open_input(path: Option<&Path>) -> Result<Box<dyn BufRead>, String> {
+    match path {
+        Some(p) if p != Path::new("-") => {
+            let f = File::open(p).map_err(|e| format!("{}: {e}", p.display()))?;
+            Ok(Box::new(BufReader::new(f)))
+        },
+        _ => Ok(Box::new(BufReader::new(io::stdin()))),
+    }
+}
+
+/// Parse the input for the specified day
+fn parse_input(day: u32, reader: &mut dyn BufRead) -> Result<Box<dyn Solution>, String> {
+    match solution::parse(day, reader) {
+        Some(result) => result.map_err(|e| e.to_string()),
+        None => Err(format!("no solution for day {day}")),
+    }
+}
+
+/// Solve one part of a puzzle, returning the answer and how long it took
+fn timed_part(solution: &dyn Solution, part: u32) -> (String, Duration) {
+    let start = Instant::now();
+    let answer = if part == 1 { solution.part1() } else { solution.part2() };
+    return (answer.to_string(), start.elapsed());
+}
+
+/// Solve a single day, printing the answer(s)
+fn run_day(options: &Options) -> Result<(), String> {
+    let day = options.day.unwrap_or_default();
+    let mut reader = open_input(options.input.as_deref())?;
+    let solution = parse_input(day, &mut reader)?;
+
+    match options.part {
+        Some(part) => println!("{}", timed_part(solution.as_ref(), part).0),
+        None => {
+            for part in 1..=2 {
+                println!("Part {part}: {}", timed_part(solution.as_ref(), part).0);
+            }
+        },
+    }
+
+    return Ok(());
+}
+
+/// Solve every day, printing the answers along with parse and solve timings
+fn run_all(options: &Options) -> Result<(), String> {
+    let data_dir = options.data_dir.clone().unwrap_or_else(|| PathBuf::from("data"));
+    let mut total = Duration::ZERO;
+
+    for day in solution::days() {
+        let path = data_dir.join(format!("day{day}_actual.txt"));
+        let mut reader = open_input(Some(&path))?;
+
+        let start = Instant::now();
+        let solution = parse_input(day, &mut reader)?;
+        let parse_time = start.elapsed();
+        println!("Day {day:2}  parse   ({parse_time:?})");
+        total += parse_time;
+
+        for part in 1..=2 {
+            let (answer, elapsed) = timed_part(solution.as_ref(), part);
+            total += elapsed;
+
+            //  Multi-line answers (like CRT images) start on their own line
+            if answer.contains('\n') {
+                println!("        part {part}  ({elapsed:?})\n{}", answer.trim_matches('\n'));
+            } else {
+                println!("        part {part}  ({elapsed:?})  {answer}");
+            }
+        }
+    }
+
+    println!("Total ({total:?})");
+    return Ok(());
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::from(2);
+        },
+    };
+
+    if options.help {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    let result = if options.all { run_all(&options) } else { run_day(&options) };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_args_day() {
+        let options = parse_args(&args("--day 3 --part 2 data/day3_example.txt")).unwrap();
+        assert_eq!(Some(3), options.day);
+        assert_eq!(Some(2), options.part);
+        assert_eq!(Some(PathBuf::from("data/day3_example.txt")), options.input);
+        assert!(!options.all);
+
+        let options = parse_args(&args("-d 1 -")).unwrap();
+        assert_eq!(Some(1), options.day);
+        assert_eq!(None, options.part);
+        assert_eq!(Some(PathBuf::from("-")), options.input);
+    }
+
+    #[test]
+    fn parse_args_all() {
+        let options = parse_args(&args("--all --data inputs")).unwrap();
+        assert!(options.all);
+        assert_eq!(Some(PathBuf::from("inputs")), options.data_dir);
+    }
+
+    #[test]
+    fn parse_args_invalid() {
+        assert!(parse_args(&args("")).is_err());
+        assert!(parse_args(&args("--day")).is_err());
+        assert!(parse_args(&args("--day x")).is_err());
+        assert!(parse_args(&args("--day 1 --part 3")).is_err());
+        assert!(parse_args(&args("--day 1 --bogus")).is_err());
+        assert!(parse_args(&args("--day 1 a.txt b.txt")).is_err());
+        assert!(parse_args(&args("--all --day 1")).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+#![allow(clippy::needless_return)]
+
+use advent_of_code_2022::day10;
+use advent_of_code_2022::debugger::{Debugger, HELP};
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: debugger PROGRAM";
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.len() != 1 || args[0] == "-h" || args[0] == "--help" {
+        eprintln!("{USAGE}\n\nCommands:\n{HELP}");
+        return ExitCode::from(2);
+    }
+
+    let program = match File::open(&args[0]) {
+        Ok(f) => day10::read_program(&mut BufReader::new(f)),
+        Err(e) => {
+            eprintln!("error: {}: {e}", args[0]);
+            return ExitCode::FAILURE;
+        },
+    };
+
+    let mut debugger = match program {
+        Ok(program) => Debugger::new(program),
+        Err(e) => {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        },
+    };
+
+    println!("{}", debugger.registers());
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("(dbg) ");
+        let _ = io::stdout().flush();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => break,
+        };
+
+        match line.trim() {
+            "q" | "quit" => break,
+            command => {
+                let output = debugger.execute(command);
+                if !output.is_empty() {
+                    println!("{output}");
+                }
+            },
+        }
+    }
+
+    return ExitCode::SUCCESS;
+}
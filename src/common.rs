@@ -1,37 +1,372 @@
-pub struct Grid {
-    grid: Box<Vec<u32>>,
+use std::fmt;
+use std::io::BufRead;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// An error encountered while reading puzzle input.  Carries enough context
+/// (day, line, column and the offending text) to report the problem and move on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Create a new error.  Line and column numbers are 1-based.
+    pub fn new(day: u32, line: usize, column: usize, text: &str, message: &str) -> Self {
+        Self {
+            day,
+            line,
+            column,
+            text: text.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// Create an error for `token`, which should be a slice of `line`.  The column
+    /// is worked out from the position of the token within the line.
+    pub fn at(day: u32, line_num: usize, line: &str, token: &str, message: &str) -> Self {
+        Self::new(day, line_num, column_of(line, token), token, message)
+    }
+
+    /// Create an error for a line that could not be read at all
+    pub fn io(day: u32, line_num: usize, err: &std::io::Error) -> Self {
+        Self::new(day, line_num, 0, "", &err.to_string())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "day {} line {} column {}: {}", self.day, self.line, self.column, self.message)?;
+        if !self.text.is_empty() {
+            write!(f, " ('{}')", self.text)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Read the whole input into a string, for readers that need to see all of it
+/// before parsing
+pub fn read_input(day: u32, reader: &mut dyn BufRead) -> Result<String, ParseError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input).map_err(|e| ParseError::io(day, 0, &e))?;
+    return Ok(input);
+}
+
+/// Find the (1-based, character) column at which `token` starts within `line`.
+/// If `token` isn't a slice of `line` the start of the line is assumed.
+fn column_of(line: &str, token: &str) -> usize {
+    let start = line.as_ptr() as usize;
+    let pos = token.as_ptr() as usize;
+
+    if pos < start || pos > start + line.len() {
+        return 1;
+    }
+
+    line[..pos - start].chars().count() + 1
+}
+
+/// A two-dimensional grid of values, stored in row-major order
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Grid<T> {
+    grid: Vec<T>,
     pub rows: usize,
     pub cols: usize
 }
 
-impl Grid {
-    /// Create a new grid with the specified dimensions
-    pub fn new(rows: usize, cols: usize) -> Self {
-        let mut grid = Box::new(Vec::new());
+/// Offsets to the 4 orthogonal neighbours of a grid location
+const NEIGHBOURS4: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
 
-        let grid_size = rows * cols;
-        for _i in 0..grid_size {
-            grid.push(0u32);
-        }
+/// Offsets to the 8 orthogonal and diagonal neighbours of a grid location
+const NEIGHBOURS8: [(isize, isize); 8] = [
+    (-1, -1), (-1, 0), (-1, 1),
+    ( 0, -1),          ( 0, 1),
+    ( 1, -1), ( 1, 0), ( 1, 1),
+];
 
-        Self { grid, rows, cols }
+impl<T: Clone + Default> Grid<T> {
+    /// Create a new grid with the specified dimensions, filled with default values
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self::filled(rows, cols, T::default())
     }
 
-    /// Resize the grid
+    /// Resize the grid.  Values that fall within both the old and new dimensions
+    /// keep their location; new locations are filled with default values.
     pub fn resize(&mut self, rows: usize, cols: usize) {
-        self.grid.resize(rows * cols, 0u32);
+        let mut grid = vec![T::default(); rows * cols];
+
+        for row in 0..rows.min(self.rows) {
+            for col in 0..cols.min(self.cols) {
+                grid[row * cols + col] = self.grid[row * self.cols + col].clone();
+            }
+        }
+
+        self.grid = grid;
         self.rows = rows;
         self.cols = cols;
     }
+}
 
-    /// Return the value at the specified grid location
-    pub fn get(&self, row: usize, col: usize) -> u32 {
-        self.grid[self.rows * row + col]
+impl<T: Clone> Grid<T> {
+    /// Create a new grid with the specified dimensions, with every location set to `value`
+    pub fn filled(rows: usize, cols: usize, value: T) -> Self {
+        Self { grid: vec![value; rows * cols], rows, cols }
+    }
+
+    /// Return a new grid with rows and columns swapped
+    pub fn transpose(&self) -> Self {
+        let mut grid = Vec::with_capacity(self.grid.len());
+        for col in 0..self.cols {
+            grid.extend(self.col(col).cloned());
+        }
+        Self { grid, rows: self.cols, cols: self.rows }
     }
 
-    /// Assign a new value to the specified grid location
-    pub fn set(&mut self, row: usize, col: usize, value: u32) {
-        self.grid[self.rows * row + col] = value;
+    /// Return a new grid rotated 90 degrees clockwise
+    pub fn rotate_cw(&self) -> Self {
+        let mut grid = Vec::with_capacity(self.grid.len());
+        for col in 0..self.cols {
+            grid.extend(self.col(col).rev().cloned());
+        }
+        Self { grid, rows: self.cols, cols: self.rows }
+    }
+
+    /// Return a new grid rotated 90 degrees counter-clockwise
+    pub fn rotate_ccw(&self) -> Self {
+        let mut grid = Vec::with_capacity(self.grid.len());
+        for col in (0..self.cols).rev() {
+            grid.extend(self.col(col).cloned());
+        }
+        Self { grid, rows: self.cols, cols: self.rows }
+    }
+}
+
+impl<T> Grid<T> {
+    /// Create a grid from a vector of values in row-major order.  Returns None if
+    /// the number of values doesn't match the dimensions.
+    pub fn from_vec(rows: usize, cols: usize, grid: Vec<T>) -> Option<Self> {
+        if grid.len() != rows * cols {
+            return None;
+        }
+        Some(Self { grid, rows, cols })
+    }
+
+    /// Parse a grid with one row per line and one value per character, using `f`
+    /// to convert each character.  Surrounding whitespace and blank lines are
+    /// ignored.  The returned error is not specific to any day (day 0).
+    pub fn parse_with<F>(s: &str, mut f: F) -> Result<Self, ParseError>
+        where F: FnMut(char) -> Option<T>
+    {
+        let mut grid = Vec::new();
+        let mut rows = 0;
+        let mut cols = 0;
+
+        for (line_num, line) in s.lines().enumerate() {
+            let trimmed_line = line.trim();
+            if trimmed_line.is_empty() {
+                continue;
+            }
+
+            let mut len = 0;
+            for (pos, c) in trimmed_line.char_indices() {
+                let value = f(c)
+                    .ok_or_else(|| ParseError::at(0, line_num + 1, line, &trimmed_line[pos..pos + c.len_utf8()], "invalid grid value"))?;
+                grid.push(value);
+                len += 1;
+            }
+
+            if rows == 0 {
+                cols = len;
+            } else if len != cols {
+                return Err(ParseError::at(0, line_num + 1, line, trimmed_line,
+                    &format!("expected {cols} columns but found {len}")));
+            }
+            rows += 1;
+        }
+
+        Ok(Self { grid, rows, cols })
+    }
+
+    /// Return a new grid by applying `f` to every value
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid<U> {
+        Grid { grid: self.grid.iter().map(f).collect(), rows: self.rows, cols: self.cols }
+    }
+
+    /// Check whether the specified location is within the grid
+    pub fn in_bounds(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols
+    }
+
+    fn offset(&self, row: usize, col: usize) -> Option<usize> {
+        if self.in_bounds(row, col) {
+            Some(self.cols * row + col)
+        } else {
+            None
+        }
+    }
+
+    /// Return the value at the specified grid location, or None if it's out of bounds
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.offset(row, col).map(|i| &self.grid[i])
+    }
+
+    /// Return a mutable reference to the value at the specified grid location, or
+    /// None if it's out of bounds
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        self.offset(row, col).map(|i| &mut self.grid[i])
+    }
+
+    /// Assign a new value to the specified grid location.  Panics if the location
+    /// is out of bounds.
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        self[(row, col)] = value;
+    }
+
+    /// Iterate over the values in the specified row
+    pub fn row(&self, row: usize) -> std::slice::Iter<'_, T> {
+        assert!(row < self.rows, "row {row} is out of bounds ({} rows)", self.rows);
+        self.grid[row * self.cols..(row + 1) * self.cols].iter()
+    }
+
+    /// Iterate over the values in the specified column
+    pub fn col(&self, col: usize) -> impl DoubleEndedIterator<Item = &T> {
+        assert!(col < self.cols, "column {col} is out of bounds ({} columns)", self.cols);
+        (0..self.rows).map(move |row| &self.grid[row * self.cols + col])
+    }
+
+    /// Iterate diagonally down and to the right, starting at the specified location
+    pub fn diagonal(&self, row: usize, col: usize) -> impl Iterator<Item = &T> {
+        (0..).map_while(move |i| self.get(row + i, col + i))
+    }
+
+    /// Iterate diagonally down and to the left, starting at the specified location
+    pub fn anti_diagonal(&self, row: usize, col: usize) -> impl Iterator<Item = &T> {
+        (0..=col).map_while(move |i| self.get(row + i, col - i))
+    }
+
+    /// Iterate over every grid location and its value, in row-major order
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let cols = self.cols;
+        self.grid.iter().enumerate().map(move |(i, v)| ((i / cols, i % cols), v))
+    }
+
+    /// Iterate over the in-bounds locations reached by applying `offsets` to a location
+    fn neighbours<'a>(&self, row: usize, col: usize, offsets: &'a [(isize, isize)]) -> impl Iterator<Item = (usize, usize)> + 'a {
+        let (rows, cols) = (self.rows, self.cols);
+        offsets.iter().filter_map(move |(dr, dc)| {
+            let r = row.checked_add_signed(*dr)?;
+            let c = col.checked_add_signed(*dc)?;
+            if r < rows && c < cols { Some((r, c)) } else { None }
+        })
+    }
+
+    /// Iterate over the locations of the (up to) 4 orthogonal neighbours of a location
+    pub fn neighbours4(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbours(row, col, &NEIGHBOURS4)
+    }
+
+    /// Iterate over the locations of the (up to) 8 orthogonal and diagonal neighbours
+    /// of a location
+    pub fn neighbours8(&self, row: usize, col: usize) -> impl Iterator<Item = (usize, usize)> {
+        self.neighbours(row, col, &NEIGHBOURS8)
+    }
+}
+
+impl<T> Grid<T> {
+    /// Render the grid as a plain (ASCII) PGM greyscale image with one pixel per
+    /// location, using `f` to convert each value to a grey level from 0 to `max_value`
+    pub fn to_pgm<F: Fn(&T) -> u16>(&self, max_value: u16, f: F) -> String {
+        let mut image = format!("P2\n{} {}\n{}\n", self.cols, self.rows, max_value);
+        for row in 0..self.rows {
+            let pixels = self.row(row).map(|v| f(v).min(max_value).to_string()).collect::<Vec<String>>();
+            image.push_str(&pixels.join(" "));
+            image.push('\n');
+        }
+        return image;
+    }
+
+    /// Render the grid as a plain (ASCII) PPM colour image with one pixel per
+    /// location, using `f` to convert each location and value to an RGB colour
+    pub fn to_ppm<F: Fn((usize, usize), &T) -> (u8, u8, u8)>(&self, f: F) -> String {
+        let mut image = format!("P3\n{} {}\n255\n", self.cols, self.rows);
+        for row in 0..self.rows {
+            let pixels = self.row(row).enumerate()
+                .map(|(col, v)| {
+                    let (r, g, b) = f((row, col), v);
+                    format!("{r} {g} {b}")
+                })
+                .collect::<Vec<String>>();
+            image.push_str(&pixels.join("  "));
+            image.push('\n');
+        }
+        return image;
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        match self.offset(row, col) {
+            Some(i) => &self.grid[i],
+            None => panic!("({row}, {col}) is out of bounds for a {}x{} grid", self.rows, self.cols),
+        }
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut T {
+        match self.offset(row, col) {
+            Some(i) => &mut self.grid[i],
+            None => panic!("({row}, {col}) is out of bounds for a {}x{} grid", self.rows, self.cols),
+        }
+    }
+}
+
+impl FromStr for Grid<char> {
+    type Err = ParseError;
+
+    /// Parse a grid of characters
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, Some)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    /// Write the grid one row per line, with no separator between values
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.rows {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for value in self.row(row) {
+                write!(f, "{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A seeded pseudo-random number generator (a 64-bit LCG), for tests that need
+/// plenty of varied but repeatable input
+#[cfg(test)]
+pub(crate) struct TestRng(u64);
+
+#[cfg(test)]
+impl TestRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Return a number in the range 0..n
+    pub(crate) fn below(&mut self, n: u64) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        return (self.0 >> 33) % n;
     }
 }
 
@@ -41,18 +376,133 @@ mod tests {
 
     #[test]
     fn grid_new() {
-        let g: Grid = Grid::new(1, 1);
-        assert_eq!(0, g.get(0, 0));
+        let g: Grid<u32> = Grid::new(1, 1);
+        assert_eq!(Some(&0), g.get(0, 0));
+    }
+
+    #[test]
+    fn parse_error_column() {
+        let line = "  move 1 from x to 2";
+        let token = &line[14..15];
+        let e = ParseError::at(5, 3, line, token, "invalid number");
+        assert_eq!(3, e.line);
+        assert_eq!(15, e.column);
+        assert_eq!("x", e.text);
+        assert_eq!("day 5 line 3 column 15: invalid number ('x')", e.to_string());
     }
 
     #[test]
     fn grid_set_get() {
-        let mut g: Grid = Grid::new(2, 2);
+        let mut g: Grid<u32> = Grid::new(2, 2);
         g.set(0, 0, 1);
         g.set(1, 1, 2);
-        assert_eq!(1, g.get(0, 0));
-        assert_eq!(0, g.get(0, 1));
-        assert_eq!(0, g.get(1, 0));
-        assert_eq!(2, g.get(1, 1));
+        assert_eq!(Some(&1), g.get(0, 0));
+        assert_eq!(Some(&0), g.get(0, 1));
+        assert_eq!(Some(&0), g.get(1, 0));
+        assert_eq!(Some(&2), g.get(1, 1));
+    }
+
+    #[test]
+    fn grid_bounds() {
+        let mut g: Grid<u32> = Grid::new(2, 3);
+        assert_eq!(None, g.get(2, 0));
+        assert_eq!(None, g.get(0, 3));
+        assert_eq!(None, g.get_mut(0, 3));
+        assert!(g.in_bounds(1, 2));
+        assert!(!g.in_bounds(2, 2));
+
+        *g.get_mut(1, 2).unwrap() = 5;
+        assert_eq!(5, g[(1, 2)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn grid_set_out_of_bounds() {
+        let mut g: Grid<u32> = Grid::new(2, 3);
+        g.set(0, 3, 1);
+    }
+
+    #[test]
+    fn grid_non_square() {
+        let mut g: Grid<u32> = Grid::new(2, 3);
+        for row in 0..2 {
+            for col in 0..3 {
+                g.set(row, col, (row * 3 + col) as u32);
+            }
+        }
+        assert_eq!(vec![3, 4, 5], g.row(1).cloned().collect::<Vec<u32>>());
+        assert_eq!(vec![2, 5], g.col(2).cloned().collect::<Vec<u32>>());
+
+        g.resize(3, 2);
+        assert_eq!("01\n34\n00", g.to_string());
+    }
+
+    #[test]
+    fn grid_from_str() {
+        let g: Grid<char> = "abc
+                             def".parse().unwrap();
+        assert_eq!(2, g.rows);
+        assert_eq!(3, g.cols);
+        assert_eq!('f', g[(1, 2)]);
+        assert_eq!("abc\ndef", g.to_string());
+
+        let err = "abc\nde".parse::<Grid<char>>().unwrap_err();
+        assert_eq!(2, err.line);
+        assert_eq!("de", err.text);
+
+        let err = Grid::parse_with("12\n3x", |c| c.to_digit(10)).unwrap_err();
+        assert_eq!(2, err.line);
+        assert_eq!(2, err.column);
+        assert_eq!("x", err.text);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn grid_diagonals() {
+        let g: Grid<char> = "abc\ndef\nghi\njkl".parse().unwrap();
+        assert_eq!("aei", g.diagonal(0, 0).collect::<String>());
+        assert_eq!("bf", g.diagonal(0, 1).collect::<String>());
+        assert_eq!("dhl", g.diagonal(1, 0).collect::<String>());
+        assert_eq!("ceg", g.anti_diagonal(0, 2).collect::<String>());
+        assert_eq!("fhj", g.anti_diagonal(1, 2).collect::<String>());
+        assert_eq!("a", g.anti_diagonal(0, 0).collect::<String>());
+    }
+
+    #[test]
+    fn grid_neighbours() {
+        let g: Grid<char> = "abc\ndef\nghi".parse().unwrap();
+        assert_eq!(vec![(0, 1), (1, 0)], g.neighbours4(0, 0).collect::<Vec<(usize, usize)>>());
+        assert_eq!(4, g.neighbours4(1, 1).count());
+        assert_eq!(3, g.neighbours8(2, 2).count());
+        assert_eq!(8, g.neighbours8(1, 1).count());
+        assert_eq!("bdfh", g.neighbours4(1, 1).map(|p| g[p]).collect::<String>());
+    }
+
+    #[test]
+    fn grid_transform() {
+        let g: Grid<char> = "abc\ndef".parse().unwrap();
+        assert_eq!("ad\nbe\ncf", g.transpose().to_string());
+        assert_eq!("da\neb\nfc", g.rotate_cw().to_string());
+        assert_eq!("cf\nbe\nad", g.rotate_ccw().to_string());
+        assert_eq!(g, g.rotate_cw().rotate_ccw());
+        assert_eq!(g, g.rotate_cw().rotate_cw().rotate_cw().rotate_cw());
+    }
+
+    #[test]
+    fn grid_iter_map() {
+        let g: Grid<char> = "ab\ncd".parse().unwrap();
+        let cells = g.iter().map(|(p, c)| (p, *c)).collect::<Vec<((usize, usize), char)>>();
+        assert_eq!(vec![((0, 0), 'a'), ((0, 1), 'b'), ((1, 0), 'c'), ((1, 1), 'd')], cells);
+
+        let upper = g.map(|c| c.to_ascii_uppercase());
+        assert_eq!("AB\nCD", upper.to_string());
+    }
+
+    #[test]
+    fn grid_images() {
+        let g: Grid<u32> = Grid::parse_with("012\n345", |c| c.to_digit(10)).unwrap();
+        assert_eq!("P2\n3 2\n4\n0 1 2\n3 4 4\n", g.to_pgm(4, |v| *v as u16));
+
+        let ppm = g.to_ppm(|(row, col), v| if (row, col) == (1, 1) { (255, 0, 0) } else { (*v as u8, 0, 0) });
+        assert_eq!("P3\n3 2\n255\n0 0 0  1 0 0  2 0 0\n3 0 0  255 0 0  5 0 0\n", ppm);
+    }
+}
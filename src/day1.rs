@@ -1,12 +1,17 @@
 use std::io::BufRead;
 
-pub fn read_elf_calories(reader: &mut dyn BufRead) -> Vec<u32> {
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 1;
+
+pub fn read_elf_calories(reader: &mut dyn BufRead) -> Result<Vec<u32>, ParseError> {
     let mut elf_calorie_vector: Vec<u32> = Vec::new();
     let mut calorie_accumulator: u32 = 0;
 
-    for line_result in reader.lines() {
+    for (line_num, line_result) in reader.lines().enumerate() {
 
-        let line = line_result.unwrap();
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let line_trimmed = line.trim();
 
         //  Accumulate calories until an empty line is found
@@ -14,7 +19,10 @@ pub fn read_elf_calories(reader: &mut dyn BufRead) -> Vec<u32> {
             elf_calorie_vector.push(calorie_accumulator);
             calorie_accumulator = 0;
         } else {
-            calorie_accumulator += line_trimmed.parse::<u32>().unwrap();
+            let calories = line_trimmed.parse::<u32>()
+                .map_err(|_| ParseError::at(DAY, line_num + 1, &line, line_trimmed, "invalid calorie count"))?;
+            calorie_accumulator = calorie_accumulator.checked_add(calories)
+                .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, line_trimmed, "calorie total is too large"))?;
         }
     }
 
@@ -22,40 +30,70 @@ pub fn read_elf_calories(reader: &mut dyn BufRead) -> Vec<u32> {
         elf_calorie_vector.push(calorie_accumulator);
     }
 
-    return elf_calorie_vector;
+    return Ok(elf_calorie_vector);
 }
 
-pub fn max_calories(elf_calorie_vector: &Vec<u32>) -> u32 {
+pub fn max_calories(elf_calorie_vector: &[u32]) -> u32 {
 
-    //  Find the largest calorie count
-    let max_calories = elf_calorie_vector.iter().max().unwrap();
+    //  Find the largest calorie count, or 0 if there are no elves
+    let max_calories = elf_calorie_vector.iter().max().copied().unwrap_or(0);
 
-    return *max_calories;
+    return max_calories;
 }
 
-pub fn top_three_calories(elf_calorie_vector: &mut Vec<u32>) -> (u32, u32, u32) {
+pub fn top_three_calories(elf_calorie_vector: &mut [u32]) -> (u32, u32, u32) {
 
     //  Sort the calorie vector, descending
     elf_calorie_vector.sort_unstable();
     elf_calorie_vector.reverse();
 
-    let elf1 = if elf_calorie_vector.len() >= 1 { elf_calorie_vector[0] } else { 0 };
+    let elf1 = if !elf_calorie_vector.is_empty() { elf_calorie_vector[0] } else { 0 };
     let elf2 = if elf_calorie_vector.len() >= 2 { elf_calorie_vector[1] } else { 0 };
     let elf3 = if elf_calorie_vector.len() >= 3 { elf_calorie_vector[2] } else { 0 };
 
     return (elf1, elf2, elf3);
 }
 
+/// Solution for day 1
+pub struct Day1 {
+    elf_calorie_vector: Vec<u32>,
+}
+
+impl Solution for Day1 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { elf_calorie_vector: read_elf_calories(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(max_calories(&self.elf_calorie_vector))
+    }
+
+    fn part2(&self) -> Answer {
+        let mut elf_calorie_vector = self.elf_calorie_vector.clone();
+        let top = top_three_calories(&mut elf_calorie_vector);
+        Box::new(top.0 + top.1 + top.2)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn no_elves() {
+        let mut buf = "".as_bytes();
+        let elf_calorie_vector = read_elf_calories(&mut buf).unwrap();
+
+        assert_eq!(0, elf_calorie_vector.len());
+        assert_eq!(0, max_calories(&elf_calorie_vector));
+    }
+
     #[test]
     fn one_elf_one_line() {
         let input =
             "1000";
         let mut buf = input.as_bytes();
-        let elf_calorie_vector = read_elf_calories(&mut buf);
+        let elf_calorie_vector = read_elf_calories(&mut buf).unwrap();
 
         assert_eq!(1, elf_calorie_vector.len());
         assert_eq!(1000, elf_calorie_vector[0]);
@@ -71,7 +109,7 @@ mod tests {
             "1000
             1000";
         let mut buf = input.as_bytes();
-        let elf_calorie_vector = read_elf_calories(&mut buf);
+        let elf_calorie_vector = read_elf_calories(&mut buf).unwrap();
 
         assert_eq!(1, elf_calorie_vector.len());
         assert_eq!(2000, elf_calorie_vector[0]);
@@ -90,7 +128,7 @@ mod tests {
             1000
             2000";
         let mut buf = input.as_bytes();
-        let elf_calorie_vector = read_elf_calories(&mut buf);
+        let elf_calorie_vector = read_elf_calories(&mut buf).unwrap();
 
         assert_eq!(2, elf_calorie_vector.len());
         assert_eq!(2000, elf_calorie_vector[0]);
@@ -110,7 +148,7 @@ mod tests {
             
             3000";
         let mut buf = input.as_bytes();
-        let mut elf_calorie_vector = read_elf_calories(&mut buf);
+        let mut elf_calorie_vector = read_elf_calories(&mut buf).unwrap();
 
         assert_eq!(3, elf_calorie_vector.len());
         assert_eq!(1000, elf_calorie_vector[0]);
@@ -126,4 +164,18 @@ mod tests {
         assert_eq!(2000, top.1);
         assert_eq!(1000, top.2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn invalid_calories() {
+        let input =
+            "1000
+            10x0";
+        let mut buf = input.as_bytes();
+        let err = read_elf_calories(&mut buf).unwrap_err();
+
+        assert_eq!(1, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(13, err.column);
+        assert_eq!("10x0", err.text);
+    }
+}
@@ -1,110 +1,744 @@
+use std::fmt;
 use std::io::BufRead;
 
+use crate::common::{self, ParseError};
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 10;
+
+/// A CPU register
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    X,
+    Y,
+}
+
+impl Register {
+    /// Return the specification of this register in the register file
+    pub fn spec(self) -> &'static RegisterSpec {
+        //  The register file is in the same order as the Register variants
+        &REGISTERS[self as usize]
+    }
+
+    /// Look up a register by its (lower case) name
+    pub fn from_name(name: &str) -> Option<Self> {
+        REGISTERS.iter().find(|spec| spec.name == name).map(|spec| spec.register)
+    }
+
+    /// Return the name of the register
+    pub fn name(self) -> &'static str {
+        self.spec().name
+    }
+}
+
+/// The definition of a register: what it's called and its value when the computer
+/// starts (or is reset)
+pub struct RegisterSpec {
+    pub register: Register,
+    pub name: &'static str,
+    pub initial: i32,
+}
+
+/// The register file, in the same order as the Register variants
+pub const REGISTERS: [RegisterSpec; 2] = [
+    RegisterSpec { register: Register::X, name: "x", initial: 1 },
+    RegisterSpec { register: Register::Y, name: "y", initial: 0 },
+];
+
+/// The number of registers in the register file
+pub const REGISTER_COUNT: usize = REGISTERS.len();
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The kinds of operand an instruction can take
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OperandKind {
+    /// A number or a register holding one
+    Value,
+    /// A register
+    Register,
+    /// An instruction index, written as a number or a label
+    Target,
+}
+
+impl OperandKind {
+    /// Whether an operand is of this kind
+    pub fn accepts(self, operand: Operand) -> bool {
+        matches!((self, operand),
+            (Self::Value, Operand::Immediate(_) | Operand::Register(_))
+            | (Self::Register, Operand::Register(_))
+            | (Self::Target, Operand::Target(_)))
+    }
+
+    /// Describe what an operand of this kind should be
+    pub fn expected(self) -> &'static str {
+        match self {
+            Self::Value => "expected a number or register",
+            Self::Register => "expected a register",
+            Self::Target => "expected a jump target",
+        }
+    }
+}
+
+/// An instruction operand
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operand {
+    Immediate(i32),
+    Register(Register),
+    Target(usize),
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Immediate(v) => write!(f, "{v}"),
+            Self::Register(r) => write!(f, "{r}"),
+            Self::Target(t) => write!(f, "{t}"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mnemonic {
     Noop,
     Addx,
+    Addy,
+    Mulx,
+    Jmp,
+    Jnz,
 }
 
-pub struct Instruction {
+impl Mnemonic {
+    /// Return the specification of this instruction in the instruction set
+    pub fn spec(self) -> &'static InstructionSpec {
+        //  The instruction set is in the same order as the Mnemonic variants
+        &INSTRUCTION_SET[self as usize]
+    }
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.spec().name)
+    }
+}
+
+/// Apply a completed instruction to the computer, returning the index of the
+/// next instruction if it isn't the following one
+pub type Effect = fn(&mut FakeComputer, &[Operand]) -> Option<usize>;
+
+/// The definition of an instruction: what it's called, what operands it takes,
+/// how many cycles it takes to complete and what it does when it completes
+pub struct InstructionSpec {
     pub mnemonic: Mnemonic,
+    pub name: &'static str,
+    pub operands: &'static [OperandKind],
     pub cycle_count: usize,
-    pub arg: i32,
+    pub effect: Effect,
+}
+
+impl InstructionSpec {
+    /// Describe how many operands the instruction takes, e.g. "addx takes 1 operand"
+    pub fn operand_count_message(&self) -> String {
+        match self.operands.len() {
+            0 => format!("{} takes no operands", self.name),
+            1 => format!("{} takes 1 operand", self.name),
+            n => format!("{} takes {n} operands", self.name),
+        }
+    }
+}
+
+/// The instruction set, in the same order as the Mnemonic variants.  Registers are
+/// 32 bits wide and arithmetic on them wraps around, as it would in hardware.
+pub const INSTRUCTION_SET: [InstructionSpec; 6] = [
+    InstructionSpec { mnemonic: Mnemonic::Noop, name: "noop", operands: &[], cycle_count: 1,
+        effect: |_, _| None },
+    InstructionSpec { mnemonic: Mnemonic::Addx, name: "addx", operands: &[OperandKind::Value], cycle_count: 2,
+        effect: |cpu, ops| { cpu.update(Register::X, ops[0], i32::wrapping_add); None } },
+    InstructionSpec { mnemonic: Mnemonic::Addy, name: "addy", operands: &[OperandKind::Value], cycle_count: 2,
+        effect: |cpu, ops| { cpu.update(Register::Y, ops[0], i32::wrapping_add); None } },
+    InstructionSpec { mnemonic: Mnemonic::Mulx, name: "mulx", operands: &[OperandKind::Value], cycle_count: 3,
+        effect: |cpu, ops| { cpu.update(Register::X, ops[0], i32::wrapping_mul); None } },
+    InstructionSpec { mnemonic: Mnemonic::Jmp, name: "jmp", operands: &[OperandKind::Target], cycle_count: 1,
+        effect: |cpu, ops| Some(cpu.target(ops[0])) },
+    InstructionSpec { mnemonic: Mnemonic::Jnz, name: "jnz", operands: &[OperandKind::Register, OperandKind::Target],
+        cycle_count: 2, effect: |cpu, ops| if cpu.value(ops[0]) != 0 { Some(cpu.target(ops[1])) } else { None } },
+];
+
+/// Find an instruction in the instruction set by name
+pub fn lookup_instruction(name: &str) -> Option<&'static InstructionSpec> {
+    INSTRUCTION_SET.iter().find(|spec| spec.name == name)
+}
+
+/// An instruction in a program.  Its operands always match its specification in
+/// the instruction set, so they can be relied on when it's run.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    mnemonic: Mnemonic,
+    operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Create an instruction, checking the number and kind of its operands against
+    /// the instruction set
+    pub fn new(mnemonic: Mnemonic, operands: Vec<Operand>) -> Result<Self, String> {
+        let spec = mnemonic.spec();
+        if operands.len() != spec.operands.len() {
+            return Err(spec.operand_count_message());
+        }
+        if let Some((_, kind)) = operands.iter().zip(spec.operands).find(|(op, kind)| !kind.accepts(**op)) {
+            return Err(kind.expected().to_string());
+        }
+
+        return Ok(Self { mnemonic, operands });
+    }
+
+    pub fn mnemonic(&self) -> Mnemonic {
+        self.mnemonic
+    }
+
+    pub fn operands(&self) -> &[Operand] {
+        &self.operands
+    }
+
+    /// The number of cycles the instruction takes, from the instruction set
+    pub fn cycle_count(&self) -> usize {
+        self.mnemonic.spec().cycle_count
+    }
+}
+
+/// Instructions are displayed as assembly source, e.g. "addx -5"
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mnemonic)?;
+        for operand in &self.operands {
+            write!(f, " {operand}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of the computer over a single cycle
+#[derive(Clone, Debug, PartialEq)]
+pub struct CycleState {
+    /// The cycle number, counting from 1
+    pub cycle: i32,
+
+    /// The index of the instruction being executed
+    pub ip: usize,
+
+    /// The instruction being executed, or None if the program has halted
+    pub instruction: Option<Instruction>,
+
+    /// The value of the X register during the cycle
+    pub x_during: i32,
+
+    /// The value of the X register after the cycle
+    pub x_after: i32,
+}
+
+impl CycleState {
+    /// The signal strength during the cycle
+    pub fn signal_strength(&self) -> i32 {
+        self.cycle.wrapping_mul(self.x_during)
+    }
+}
+
+/// Something that's told about each cycle a FakeComputer runs.  Any closure taking
+/// a &CycleState is an observer.
+pub trait CycleObserver {
+    fn on_cycle(&mut self, state: &CycleState);
+}
+
+impl<F: FnMut(&CycleState)> CycleObserver for F {
+    fn on_cycle(&mut self, state: &CycleState) {
+        self(state)
+    }
+}
+
+/// An iterator over the cycles run by a FakeComputer.  It ends when the program
+/// halts if the halt policy is Stop, and never ends otherwise.
+pub struct Cycles<'a> {
+    cpu: &'a mut FakeComputer,
+}
+
+impl Iterator for Cycles<'_> {
+    type Item = CycleState;
+
+    fn next(&mut self) -> Option<CycleState> {
+        self.cpu.step()
+    }
+}
+
+/// The width of the CRT, in pixels
+pub const CRT_WIDTH: usize = 40;
+
+/// The height of the CRT, in pixels
+pub const CRT_HEIGHT: usize = 6;
+
+/// What a FakeComputer does once its program has halted
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum HaltPolicy {
+    /// Stop running cycles
+    #[default]
+    Stop,
+    /// Keep running cycles, without changing any registers
+    Idle,
+    /// Start the program again from the first instruction, keeping the registers
+    Loop,
 }
 
 pub struct FakeComputer {
     pub cycle: i32,
     pub ip: usize,
     pub ic: usize,
-    pub x: i32,
+    pub registers: [i32; REGISTER_COUNT],
     pub program: Vec<Instruction>,
+    pub halt_policy: HaltPolicy,
 }
 
 impl FakeComputer {
     pub fn new(program: Vec<Instruction>) -> Self {
-        Self { cycle: 1, ip: 0, ic: 0, x: 1, program }
+        Self { cycle: 1, ip: 0, ic: 0, registers: Self::initial_registers(), program, halt_policy: HaltPolicy::Stop }
+    }
+
+    /// Create a computer with the specified halt policy
+    pub fn with_halt_policy(program: Vec<Instruction>, halt_policy: HaltPolicy) -> Self {
+        Self { halt_policy, ..Self::new(program) }
+    }
+
+    /// Whether the program has halted, by running past its last instruction
+    pub fn halted(&self) -> bool {
+        self.ip >= self.program.len()
     }
 
     pub fn reset(&mut self) {
         self.cycle = 1;
         self.ip = 0;
         self.ic = 0;
-        self.x = 1;
+        self.registers = Self::initial_registers();
     } 
 
+    /// The values of the registers when the computer starts
+    fn initial_registers() -> [i32; REGISTER_COUNT] {
+        REGISTERS.map(|spec| spec.initial)
+    }
+
+    /// Return the value of a register
+    pub fn register(&self, register: Register) -> i32 {
+        self.registers[register as usize]
+    }
+
+    /// Return the value of the X register, which positions the CRT sprite
+    pub fn x(&self) -> i32 {
+        self.register(Register::X)
+    }
+
+    /// Combine the value of a register with the value of an operand, storing the
+    /// result in the register
+    fn update(&mut self, register: Register, operand: Operand, op: fn(i32, i32) -> i32) {
+        self.registers[register as usize] = op(self.register(register), self.value(operand));
+    }
+
+    /// Return the value of an immediate or register operand
+    pub fn value(&self, operand: Operand) -> i32 {
+        match operand {
+            Operand::Immediate(v) => v,
+            Operand::Register(r) => self.register(r),
+            Operand::Target(t) => t as i32,
+        }
+    }
+
+    /// Return the instruction index of a jump target operand
+    fn target(&self, operand: Operand) -> usize {
+        match operand {
+            Operand::Target(t) => t,
+            _ => self.value(operand) as usize,
+        }
+    }
+
+    /// Run for the specified number of cycles, or until the program halts if the
+    /// halt policy is Stop
     pub fn run(&mut self, count: i32) {
-        //println!("running for {count} cycles");
-        let end_cycle = self.cycle + count;
-
-        while self.cycle < end_cycle {
-            //println!("doing cycle {0}", self.cycle);
-            self.ic += 1;
-
-            if self.ic == self.program[self.ip].cycle_count {
-                //  This instruction is complete, apply it
-                match self.program[self.ip].mnemonic {
-                    Mnemonic::Noop => {
-                        //  do nothing
-                    },
-                    Mnemonic::Addx => {
-                        //  add the argument to register x
-                        self.x += self.program[self.ip].arg;
-                    },
-                }
+        for _ in 0..count {
+            if self.step().is_none() {
+                break;
+            }
+        }
+    }
 
-                self.ip += 1;
-                self.ic = 0;
-                //println!("next instruction {0}", self.ip);
+    /// Run for the specified number of cycles, passing the state of each cycle to
+    /// the observer
+    pub fn run_observed(&mut self, count: i32, observer: &mut dyn CycleObserver) {
+        for _ in 0..count {
+            match self.step() {
+                Some(state) => observer.on_cycle(&state),
+                None => break,
             }
+        }
+    }
 
-            self.cycle += 1;
+    /// Run until the program halts, returning the total number of cycles run since
+    /// the computer was started (or reset).  Never returns if the program loops
+    /// forever.
+    pub fn run_until_halt(&mut self) -> i32 {
+        while !self.halted() {
+            self.step();
         }
+
+        return self.cycle - 1;
+    }
+
+    /// Run a single cycle, returning what happened during it.  Once the program has
+    /// halted, what happens depends on the halt policy; with Stop no cycle is run
+    /// and None is returned.
+    pub fn step(&mut self) -> Option<CycleState> {
+        if self.halted() {
+            match self.halt_policy {
+                HaltPolicy::Stop => return None,
+                HaltPolicy::Loop if !self.program.is_empty() => self.ip = 0,
+                _ => {
+                    let state = CycleState {
+                        cycle: self.cycle, ip: self.ip, instruction: None, x_during: self.x(), x_after: self.x(),
+                    };
+                    self.cycle += 1;
+                    return Some(state);
+                },
+            }
+        }
+
+        let instruction = self.program[self.ip].clone();
+        let start = CycleState {
+            cycle: self.cycle, ip: self.ip, instruction: Some(instruction), x_during: self.x(), x_after: self.x(),
+        };
+        self.ic += 1;
+
+        if self.ic == self.program[self.ip].cycle_count() {
+            //  This instruction is complete, apply it
+            let instruction = &self.program[self.ip];
+            let effect = instruction.mnemonic.spec().effect;
+            let operands = instruction.operands.clone();
+
+            self.ip = effect(self, &operands).unwrap_or(self.ip + 1);
+            self.ic = 0;
+        }
+
+        self.cycle += 1;
+        return Some(CycleState { x_after: self.x(), ..start });
+    }
+
+    /// Iterate over the cycles run from now until the end of the program
+    pub fn cycles(&mut self) -> Cycles<'_> {
+        Cycles { cpu: self }
+    }
+
+    /// Sum the signal strengths during the specified cycles, which must be in
+    /// ascending order and not before the current cycle
+    pub fn signal_strength_sum(&mut self, cycles: &[i32]) -> i32 {
+        let last = cycles.last().copied().unwrap_or(0);
+        return self.cycles()
+            .take_while(|state| state.cycle <= last)
+            .filter(|state| cycles.contains(&state.cycle))
+            .map(|state| state.signal_strength())
+            .fold(0, i32::wrapping_add);
     }
 
     pub fn get_signal_strength(&self) -> i32 {
-        self.cycle * self.x
+        self.cycle.wrapping_mul(self.x())
     }
 
-    pub fn render_display(&mut self) {
-        while self.cycle <= 240 {
-            let c = (self.cycle - 1) % 40;
-            if c == 0 {
-                println!("");
+    /// Run the computer for one frame of the default size CRT, returning the image
+    /// drawn, one string per row
+    pub fn render_crt(&mut self) -> Vec<String> {
+        self.render_crt_sized(CRT_WIDTH, CRT_HEIGHT)
+    }
+
+    /// Run the computer for one frame of a CRT of the specified size, starting from
+    /// the current cycle, and return the image drawn, one string per row.  A pixel
+    /// is lit ('#') if the 3 pixel wide sprite centred on X covers it.
+    pub fn render_crt_sized(&mut self, width: usize, height: usize) -> Vec<String> {
+        let mut frame: Vec<String> = Vec::new();
+
+        for _ in 0..height {
+            let mut row = String::new();
+            for c in 0..width as i32 {
+                if ((c - 1) <= self.x()) && (self.x() <= (c + 1)) {
+                    row.push('#');
+                } else {
+                    row.push('.');
+                }
+
+                self.run(1);
             }
+            frame.push(row);
+        }
+
+        return frame;
+    }
+
+    /// Run the computer for one frame, printing the image drawn on the CRT
+    pub fn render_display(&mut self) {
+        for row in self.render_crt() {
+            println!();
+            print!("{row}");
+        }
+    }
+}
+
+/// The width of a letter drawn on the CRT, not counting the gap after it
+const GLYPH_WIDTH: usize = 4;
+
+/// The letters of the font used to draw on the CRT, each 4 pixels wide and 6 high
+const FONT: [(char, [&str; CRT_HEIGHT]); 16] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
 
-            if ((c - 1) <= self.x) && (self.x <= (c + 1)) {
-                print!("#");
-            } else {
-                print!(".");
+/// Read the letters drawn on a CRT frame (as returned by render_crt).  Letters are
+/// 4 pixels wide with a 1 pixel gap between them.  Errors give the row and column
+/// of the problem, with the text of an unrecognised letter's rows joined by '/'.
+pub fn ocr<S: AsRef<str>>(frame: &[S]) -> Result<String, ParseError> {
+    if frame.len() != CRT_HEIGHT {
+        let message = format!("expected {CRT_HEIGHT} rows but found {}", frame.len());
+        return Err(ParseError::new(DAY, frame.len().min(CRT_HEIGHT) + 1, 1, "", &message));
+    }
+
+    let rows = frame.iter().map(|r| r.as_ref().chars().collect::<Vec<char>>()).collect::<Vec<Vec<char>>>();
+    let width = rows[0].len();
+    for (i, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            let message = format!("expected {width} columns but found {}", row.len());
+            return Err(ParseError::new(DAY, i + 1, 1, frame[i].as_ref(), &message));
+        }
+    }
+
+    //  The gap after the last letter may be left off
+    if width % (GLYPH_WIDTH + 1) != 0 && width % (GLYPH_WIDTH + 1) != GLYPH_WIDTH {
+        return Err(ParseError::new(DAY, 1, 1, frame[0].as_ref(), "frame width isn't a whole number of letters"));
+    }
+
+    let mut text = String::new();
+    for start in (0..width).step_by(GLYPH_WIDTH + 1) {
+        let glyph = rows.iter()
+            .map(|row| row[start..start + GLYPH_WIDTH].iter().collect::<String>())
+            .collect::<Vec<String>>();
+
+        match FONT.iter().find(|(_, g)| *g == glyph.as_slice()) {
+            Some((letter, _)) => text.push(*letter),
+            None => return Err(ParseError::new(DAY, 1, start + 1, &glyph.join("/"), "unrecognised letter")),
+        }
+    }
+
+    return Ok(text);
+}
+
+/// Parse an operand of the specified kind.  Targets that are labels are resolved
+/// using the labels found by the assembler's first pass.
+fn parse_operand(token: &str, kind: OperandKind, labels: &[(String, usize)]) -> Result<Operand, &'static str> {
+    match kind {
+        OperandKind::Value => {
+            if let Some(r) = Register::from_name(token) {
+                return Ok(Operand::Register(r));
+            }
+            token.parse::<i32>().map(Operand::Immediate).map_err(|_| kind.expected())
+        },
+        OperandKind::Register => Register::from_name(token).map(Operand::Register).ok_or(kind.expected()),
+        OperandKind::Target => {
+            if let Ok(t) = token.parse::<usize>() {
+                return Ok(Operand::Target(t));
             }
-            
-            self.run(1);
+            labels.iter().find(|(l, _)| l == token).map(|(_, t)| Operand::Target(*t)).ok_or("undefined label")
+        },
+    }
+}
+
+/// Whether a label name is valid: a letter or underscore followed by letters,
+/// digits and underscores
+fn valid_label(label: &str) -> bool {
+    let mut chars = label.chars();
+    return chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+}
+
+/// Split a line of assembly into an optional label and the instruction tokens,
+/// ignoring comments (from '#' or ';' to the end of the line)
+fn split_line(line: &str) -> (Option<&str>, Vec<&str>) {
+    let code = line.split(['#', ';']).next().unwrap_or("");
+    let mut tokens = code.split_whitespace().collect::<Vec<&str>>();
+
+    let mut label = None;
+    if let Some(first) = tokens.first() {
+        if let Some(l) = first.strip_suffix(':') {
+            label = Some(l);
+            tokens.remove(0);
         }
     }
+
+    return (label, tokens);
 }
 
-/// Read input movements into a vector
-pub fn read_program(reader: &mut dyn BufRead) -> Vec<Instruction> {
+/// Assemble a program.  Each line holds at most one instruction, optionally
+/// preceded by a label ("loop: addx -1"), and may end with a comment starting with
+/// '#' or ';'.  Jump targets may be labels or instruction indexes.  A jump to the
+/// end of the program is allowed; beyond it is not.
+pub fn assemble(source: &str) -> Result<Vec<Instruction>, ParseError> {
+    //  First pass: find the instruction index of each label
+    let mut labels: Vec<(String, usize)> = Vec::new();
+    let mut count = 0;
+    for (line_num, line) in source.lines().enumerate() {
+        let (label, tokens) = split_line(line);
+
+        if let Some(l) = label {
+            if !valid_label(l) {
+                return Err(ParseError::at(DAY, line_num + 1, line, l, "invalid label"));
+            }
+            if labels.iter().any(|(existing, _)| existing == l) {
+                return Err(ParseError::at(DAY, line_num + 1, line, l, "duplicate label"));
+            }
+            labels.push((l.to_string(), count));
+        }
+
+        if !tokens.is_empty() {
+            count += 1;
+        }
+    }
+
+    //  Second pass: assemble the instructions
     let mut instructions: Vec<Instruction> = Vec::new();
-    
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
-        let trimmed_line = line.trim();
+    for (line_num, line) in source.lines().enumerate() {
+        let (_, tokens) = split_line(line);
+        if tokens.is_empty() {
+            continue;
+        }
+
+        let spec = lookup_instruction(tokens[0])
+            .ok_or_else(|| ParseError::at(DAY, line_num + 1, line, tokens[0], "unknown instruction"))?;
 
-        let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-        
-        match tokens[0] {
-            "noop" => {
-                instructions.push(Instruction { mnemonic: Mnemonic::Noop, cycle_count: 1, arg: 0 });
-            },
-            "addx" => {
-                let arg = tokens[1].parse::<i32>().unwrap();
-                instructions.push(Instruction { mnemonic: Mnemonic::Addx, cycle_count: 2, arg });
-            },
-            _ => panic!("invalid instruction"),
+        if tokens.len() != spec.operands.len() + 1 {
+            let token = tokens.get(spec.operands.len() + 1).unwrap_or(&tokens[0]);
+            return Err(ParseError::at(DAY, line_num + 1, line, token, &spec.operand_count_message()));
+        }
+
+        let mut operands = Vec::new();
+        for (token, kind) in tokens[1..].iter().zip(spec.operands) {
+            let operand = parse_operand(token, *kind, &labels)
+                .map_err(|e| ParseError::at(DAY, line_num + 1, line, token, e))?;
+            if let Operand::Target(t) = operand {
+                if t > count {
+                    return Err(ParseError::at(DAY, line_num + 1, line, token, "jump target out of range"));
+                }
+            }
+            operands.push(operand);
         }
+
+        let instruction = Instruction::new(spec.mnemonic, operands)
+            .map_err(|e| ParseError::at(DAY, line_num + 1, line, tokens[0], &e))?;
+        instructions.push(instruction);
     }
 
-    return instructions;
+    return Ok(instructions);
+}
+
+/// The most cycles run to work out the annotations for a disassembly
+const ANNOTATION_LIMIT: usize = 1_000_000;
+
+/// Turn a program back into source text that assemble will read back into the
+/// same program, one instruction per line.  Jump targets are written as
+/// instruction indexes.  If `annotate` is set, each instruction is followed by a
+/// comment giving the cycle it first starts on and the value of X after it first
+/// completes, from running the program for at most ANNOTATION_LIMIT cycles
+/// (instructions that never run have none).
+pub fn disassemble(program: &[Instruction], annotate: bool) -> String {
+    let lines = program.iter().map(|i| i.to_string()).collect::<Vec<String>>();
+    if !annotate {
+        return lines.iter().map(|l| format!("{l}\n")).collect();
+    }
+
+    //  Run the program to find when each instruction starts and what X is after it
+    let mut starts: Vec<Option<i32>> = vec![None; program.len()];
+    let mut x_after: Vec<Option<i32>> = vec![None; program.len()];
+    let mut cpu = FakeComputer::new(program.to_vec());
+    for _ in 0..ANNOTATION_LIMIT {
+        if cpu.halted() {
+            break;
+        }
+
+        let ip = cpu.ip;
+        if cpu.ic == 0 && starts[ip].is_none() {
+            starts[ip] = Some(cpu.cycle);
+        }
+        cpu.step();
+        if cpu.ic == 0 && x_after[ip].is_none() {
+            x_after[ip] = Some(cpu.x());
+        }
+    }
+
+    let width = lines.iter().map(|l| l.len()).max().unwrap_or(0);
+    let mut source = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        match (starts[i], x_after[i]) {
+            (Some(cycle), Some(x)) => source.push_str(&format!("{line:width$}  ; cycle {cycle}, x {x}\n")),
+            (Some(cycle), None) => source.push_str(&format!("{line:width$}  ; cycle {cycle}\n")),
+            _ => source.push_str(&format!("{line}\n")),
+        }
+    }
+
+    return source;
+}
+
+/// Read program instructions into a vector
+pub fn read_program(reader: &mut dyn BufRead) -> Result<Vec<Instruction>, ParseError> {
+    let source = common::read_input(DAY, reader)?;
+    return assemble(&source);
+}
+
+/// Solution for day 10
+pub struct Day10 {
+    program: Vec<Instruction>,
+}
+
+impl Solution for Day10 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { program: read_program(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut cpu = FakeComputer::new(self.program.clone());
+
+        //  Signal strengths are measured *during* cycles 20, 60, ... 220
+        Box::new(cpu.signal_strength_sum(&[20, 60, 100, 140, 180, 220]))
+    }
+
+    fn part2(&self) -> Answer {
+        let mut cpu = FakeComputer::new(self.program.clone());
+
+        //  The answer is the letters drawn on the CRT, or the image itself if it
+        //  isn't made of letters
+        let frame = cpu.render_crt();
+        let image = match ocr(&frame) {
+            Ok(letters) => letters,
+            Err(_) => format!("\n{}", frame.join("\n")),
+        };
+
+        Box::new(image)
+    }
 }
 
 #[cfg(test)]
@@ -115,16 +749,16 @@ mod tests {
     fn run1() {
         let input = "addx 15";
         let mut buf = input.as_bytes();
-        let program = read_program(&mut buf);
+        let program = read_program(&mut buf).unwrap();
         assert_eq!(1, program.len());
 
         let mut cpu = FakeComputer::new(program);
 
         cpu.run(1);
-        assert_eq!(1, cpu.x);
+        assert_eq!(1, cpu.x());
 
         cpu.run(1);
-        assert_eq!(16, cpu.x);
+        assert_eq!(16, cpu.x());
     }
 
     #[test]
@@ -132,16 +766,16 @@ mod tests {
         let input = "addx 15
             addx -11";
         let mut buf = input.as_bytes();
-        let program = read_program(&mut buf);
+        let program = read_program(&mut buf).unwrap();
         assert_eq!(2, program.len());
 
         let mut cpu = FakeComputer::new(program);
 
         cpu.run(2);
-        assert_eq!(16, cpu.x);
+        assert_eq!(16, cpu.x());
 
         cpu.run(2);
-        assert_eq!(5, cpu.x);
+        assert_eq!(5, cpu.x());
     }
 
     #[test]
@@ -150,18 +784,356 @@ mod tests {
             noop
             addx -11";
         let mut buf = input.as_bytes();
-        let program = read_program(&mut buf);
+        let program = read_program(&mut buf).unwrap();
         assert_eq!(3, program.len());
 
         let mut cpu = FakeComputer::new(program);
 
         cpu.run(2);
-        assert_eq!(16, cpu.x);
+        assert_eq!(16, cpu.x());
+
+        cpu.run(2);
+        assert_eq!(16, cpu.x());
+
+        cpu.run(1);
+        assert_eq!(5, cpu.x());
+    }
+
+    #[test]
+    fn invalid_program() {
+        let input = "addx 15
+            jump 3";
+        let mut buf = input.as_bytes();
+        let err = read_program(&mut buf).err().unwrap();
+        assert_eq!(10, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(13, err.column);
+        assert_eq!("jump", err.text);
+
+        let input = "addx fifteen";
+        let mut buf = input.as_bytes();
+        let err = read_program(&mut buf).err().unwrap();
+        assert_eq!(1, err.line);
+        assert_eq!(6, err.column);
+        assert_eq!("fifteen", err.text);
+
+        let input = "addx";
+        let mut buf = input.as_bytes();
+        let err = read_program(&mut buf).err().unwrap();
+        assert_eq!(1, err.line);
+        assert_eq!("addx", err.text);
+    }
+
+    #[test]
+    fn instruction_set_order() {
+        for (i, spec) in INSTRUCTION_SET.iter().enumerate() {
+            assert_eq!(i, spec.mnemonic as usize);
+            assert_eq!(spec.name, spec.mnemonic.spec().name);
+            assert!(spec.cycle_count > 0, "{}", spec.name);
+        }
+
+        for (i, spec) in REGISTERS.iter().enumerate() {
+            assert_eq!(i, spec.register as usize);
+            assert_eq!(Some(spec.register), Register::from_name(spec.name));
+        }
+    }
+
+    #[test]
+    fn instruction_operands() {
+        let jnz = Instruction::new(Mnemonic::Jnz, vec![Operand::Register(Register::Y), Operand::Target(0)]).unwrap();
+        assert_eq!((Mnemonic::Jnz, 2), (jnz.mnemonic(), jnz.cycle_count()));
+        assert_eq!(&[Operand::Register(Register::Y), Operand::Target(0)], jnz.operands());
 
+        let errors = [
+            (Mnemonic::Jnz, vec![], "jnz takes 2 operands"),
+            (Mnemonic::Noop, vec![Operand::Immediate(1)], "noop takes no operands"),
+            (Mnemonic::Jnz, vec![Operand::Immediate(1), Operand::Target(0)], "expected a register"),
+            (Mnemonic::Addx, vec![Operand::Target(1)], "expected a number or register"),
+            (Mnemonic::Jmp, vec![Operand::Register(Register::X)], "expected a jump target"),
+        ];
+        for (mnemonic, operands, message) in errors {
+            assert_eq!(Err(message.to_string()), Instruction::new(mnemonic, operands), "{mnemonic}");
+        }
+    }
+
+    #[test]
+    fn extended_instructions() {
+        let input = "addy 3         # y = 3
+            loop:   addx 2      ; x += 2, twice per loop
+                    addx x
+                    addy -1
+                    jnz y loop
+                    mulx y
+            end:";
+        let program = assemble(input).unwrap();
+        assert_eq!(6, program.len());
+        assert_eq!(vec![Operand::Register(Register::Y), Operand::Target(1)], program[4].operands());
+
+        let mut cpu = FakeComputer::new(program);
         cpu.run(2);
-        assert_eq!(16, cpu.x);
+        assert_eq!(3, cpu.register(Register::Y));
+
+        //  Each time round the loop takes 2 + 2 + 2 + 2 cycles
+        cpu.run(8);
+        assert_eq!((6, 2, 1), (cpu.x(), cpu.register(Register::Y), cpu.ip));
+        cpu.run(16);
+        assert_eq!((36, 0, 5), (cpu.x(), cpu.register(Register::Y), cpu.ip));
 
+        //  x * y
+        cpu.run(3);
+        assert_eq!((0, 6), (cpu.x(), cpu.ip));
+    }
+
+    #[test]
+    fn jumps() {
+        let program = assemble("jmp 2\naddx 100\naddx 5\njmp skip\naddx 100\nskip: noop").unwrap();
+        let mut cpu = FakeComputer::new(program);
         cpu.run(1);
-        assert_eq!(5, cpu.x);
+        assert_eq!(2, cpu.ip);
+        cpu.run(3);
+        assert_eq!((6, 5), (cpu.x(), cpu.ip));
+    }
+
+    #[test]
+    fn assembler_errors() {
+        let errors = [
+            ("noop\nfoo 1", 2, 1, "foo", "unknown instruction"),
+            ("noop 1", 1, 6, "1", "noop takes no operands"),
+            ("addx", 1, 1, "addx", "addx takes 1 operand"),
+            ("jnz x", 1, 1, "jnz", "jnz takes 2 operands"),
+            ("addx 1 2", 1, 8, "2", "addx takes 1 operand"),
+            ("addx z", 1, 6, "z", "expected a number or register"),
+            ("jnz 1 0", 1, 5, "1", "expected a register"),
+            ("jmp nowhere", 1, 5, "nowhere", "undefined label"),
+            ("jmp 3\nnoop", 1, 5, "3", "jump target out of range"),
+            ("a: noop\n  a: noop", 2, 3, "a", "duplicate label"),
+            ("1a: noop", 1, 1, "1a", "invalid label"),
+        ];
+
+        for (input, line, column, text, message) in errors {
+            let err = assemble(input).err().unwrap();
+            assert_eq!((10, line, column, text, message),
+                (err.day, err.line, err.column, err.text.as_str(), err.message.as_str()), "{input}");
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn render_crt_sizes() {
+        //  x moves 2 pixels right every 4 cycles, staying 1 pixel behind the beam
+        let program = assemble("noop\nnoop\naddx 2\n".repeat(60).as_str()).unwrap();
+        let mut cpu = FakeComputer::new(program);
+        assert_eq!(vec!["###.", "..##"], cpu.render_crt_sized(4, 2));
+        assert_eq!(9, cpu.cycle);
+
+        cpu.reset();
+        let frame = cpu.render_crt();
+        assert_eq!(CRT_HEIGHT, frame.len());
+        assert!(frame.iter().all(|row| row.len() == CRT_WIDTH));
+        assert_eq!(241, cpu.cycle);
+
+        cpu.reset();
+        assert!(cpu.render_crt_sized(0, 0).is_empty());
+        assert_eq!(1, cpu.cycle);
+    }
+
+    #[test]
+    fn ocr_letters() {
+        let frame = [
+            ".##..###...##..####.####..##..#..#..###",
+            "#..#.#..#.#..#.#....#....#..#.#..#...#.",
+            "#..#.###..#....###..###..#....####...#.",
+            "####.#..#.#....#....#....#.##.#..#...#.",
+            "#..#.#..#.#..#.#....#....#..#.#..#...#.",
+            "#..#.###...##..####.#.....###.#..#.###.",
+        ];
+        let err = ocr(&frame).err().unwrap();
+        assert_eq!(36, err.column);
+        assert_eq!(".###/..#./..#./..#./..#./###.", err.text);
+        assert_eq!("unrecognised letter", err.message);
+
+        let frame = frame.iter().map(|r| &r[..34]).collect::<Vec<&str>>();
+        assert_eq!("ABCEFGH", ocr(&frame).unwrap());
+
+        let mut font = vec![String::new(); CRT_HEIGHT];
+        for (_, glyph) in FONT {
+            for (row, g) in font.iter_mut().zip(glyph) {
+                row.push_str(g);
+                row.push('.');
+            }
+        }
+        assert_eq!("ABCEFGHJKLOPRSUZ", ocr(&font).unwrap());
+    }
+
+    #[test]
+    fn ocr_errors() {
+        let err = ocr(&["####"; 5]).err().unwrap();
+        assert_eq!((6, "expected 6 rows but found 5"), (err.line, err.message.as_str()));
+
+        let err = ocr(&["####", "#...", "###.", "#..", "#...", "####"]).err().unwrap();
+        assert_eq!((4, "expected 4 columns but found 3"), (err.line, err.message.as_str()));
+
+        let err = ocr(&["###"; 6]).err().unwrap();
+        assert_eq!("frame width isn't a whole number of letters", err.message);
+
+        assert_eq!("", ocr(&[""; 6]).unwrap());
+    }
+
+    #[test]
+    fn cycle_states() {
+        let program = assemble("noop\naddx 3\naddx -5").unwrap();
+        let mut cpu = FakeComputer::new(program);
+
+        let states = cpu.cycles().collect::<Vec<CycleState>>();
+        let instruction = |ip: usize| Some(cpu.program[ip].clone());
+        assert_eq!(vec![
+            CycleState { cycle: 1, ip: 0, instruction: instruction(0), x_during: 1, x_after: 1 },
+            CycleState { cycle: 2, ip: 1, instruction: instruction(1), x_during: 1, x_after: 1 },
+            CycleState { cycle: 3, ip: 1, instruction: instruction(1), x_during: 1, x_after: 4 },
+            CycleState { cycle: 4, ip: 2, instruction: instruction(2), x_during: 4, x_after: 4 },
+            CycleState { cycle: 5, ip: 2, instruction: instruction(2), x_during: 4, x_after: -1 },
+        ], states);
+        assert_eq!("addx -5", states[4].instruction.as_ref().unwrap().to_string());
+        assert_eq!(6, cpu.cycle);
+        assert_eq!(None, cpu.cycles().next());
+
+        cpu.reset();
+        let noop = Instruction::new(Mnemonic::Noop, Vec::new()).unwrap();
+        assert_eq!(Some(CycleState { cycle: 1, ip: 0, instruction: Some(noop), x_during: 1, x_after: 1 }), cpu.step());
+        assert_eq!(4 * 4 + 5 * 4, cpu.signal_strength_sum(&[4, 5]));
+        assert_eq!(6, cpu.cycle);
+    }
+
+    #[test]
+    fn observers() {
+        let program = assemble("addx 2\naddx 3\nnoop\nnoop").unwrap();
+        let mut cpu = FakeComputer::new(program);
+
+        let mut changes: Vec<(i32, i32)> = Vec::new();
+        cpu.run_observed(4, &mut |state: &CycleState| {
+            if state.x_after != state.x_during {
+                changes.push((state.cycle, state.x_after));
+            }
+        });
+        assert_eq!(vec![(2, 3), (4, 6)], changes);
+
+        struct Counter(usize);
+        impl CycleObserver for Counter {
+            fn on_cycle(&mut self, _state: &CycleState) {
+                self.0 += 1;
+            }
+        }
+
+        let mut counter = Counter(0);
+        cpu.run_observed(2, &mut counter);
+        assert_eq!(2, counter.0);
+
+        //  Running past the end of the program stops at the end
+        cpu.run_observed(3, &mut counter);
+        assert_eq!(2, counter.0);
+        cpu.run(10);
+        assert_eq!(7, cpu.cycle);
+        assert_eq!(None, cpu.step());
+    }
+
+    #[test]
+    fn register_overflow_wraps() {
+        //  Each pass round the loop takes four cycles and triples X, which soon
+        //  overflows an i32
+        let program = assemble("addx 1\nloop: mulx 3\njmp loop").unwrap();
+        let mut cpu = FakeComputer::new(program);
+        cpu.run(2 + 4 * 40);
+        assert_eq!(2i32.wrapping_mul(3i32.wrapping_pow(40)), cpu.x());
+        assert_eq!(3i32.wrapping_pow(40).wrapping_mul(2).wrapping_mul(cpu.cycle), cpu.get_signal_strength());
+
+        cpu.reset();
+        let strengths = cpu.cycles().take(1000).map(|state| state.signal_strength()).collect::<Vec<i32>>();
+        assert_eq!(1000, strengths.len());
+
+        let program = assemble("addy 2147483647\naddy 1").unwrap();
+        let mut cpu = FakeComputer::new(program);
+        cpu.run(4);
+        assert_eq!(i32::MIN, cpu.register(Register::Y));
+    }
+
+    #[test]
+    fn halting() {
+        let program = assemble("addx 2\nnoop").unwrap();
+        let mut cpu = FakeComputer::new(program.clone());
+        assert!(!cpu.halted());
+        assert_eq!(3, cpu.run_until_halt());
+        assert!(cpu.halted());
+        assert_eq!(3, cpu.run_until_halt());
+
+        //  Stop: no more cycles are run
+        assert_eq!(None, cpu.step());
+        cpu.run(10);
+        assert_eq!((4, 3), (cpu.cycle, cpu.x()));
+        assert_eq!(vec!["..#", "..#"], cpu.render_crt_sized(3, 2));
+
+        //  Idle: cycles run, but nothing changes
+        let mut cpu = FakeComputer::with_halt_policy(program.clone(), HaltPolicy::Idle);
+        cpu.run(10);
+        assert_eq!((11, 2, 3), (cpu.cycle, cpu.ip, cpu.x()));
+        assert_eq!(Some(CycleState { cycle: 11, ip: 2, instruction: None, x_during: 3, x_after: 3 }), cpu.step());
+        cpu.reset();
+        assert_eq!(3, cpu.run_until_halt());
+
+        //  Loop: the program starts again with the same registers
+        let mut cpu = FakeComputer::with_halt_policy(program, HaltPolicy::Loop);
+        cpu.run(9);
+        assert_eq!((10, 2, 7), (cpu.cycle, cpu.ip, cpu.x()));
+        assert_eq!(3, cpu.cycles().take(4).filter(|s| s.x_after > 7).count());
+
+        //  An empty program halts straight away
+        let mut cpu = FakeComputer::with_halt_policy(Vec::new(), HaltPolicy::Loop);
+        assert_eq!(0, cpu.run_until_halt());
+        cpu.run(2);
+        assert_eq!(3, cpu.cycle);
+    }
+
+    #[test]
+    fn display() {
+        let program = assemble("noop\naddx -5\nmulx y\nl: jnz x l").unwrap();
+        assert_eq!("noop", program[0].to_string());
+        assert_eq!("addx -5", program[1].to_string());
+        assert_eq!("mulx y", program[2].to_string());
+        assert_eq!("jnz x 3", program[3].to_string());
+        assert_eq!("jnz", Mnemonic::Jnz.to_string());
+        assert_eq!(Instruction::new(Mnemonic::Addx, vec![Operand::Immediate(-5)]).unwrap(), program[1]);
+        assert_ne!(program[0], program[1]);
+        assert_eq!("Instruction { mnemonic: Noop, operands: [] }", format!("{:?}", program[0]));
+    }
+
+    #[test]
+    fn disassembly() {
+        let source = "noop
+            start: addx 3
+            addy 1   # counter
+            jnz x end
+            addx 100
+            end: noop";
+        let program = assemble(source).unwrap();
+
+        let text = disassemble(&program, false);
+        assert_eq!("noop\naddx 3\naddy 1\njnz x 5\naddx 100\nnoop\n", text);
+        assert_eq!(program, assemble(&text).unwrap());
+
+        let text = disassemble(&program, true);
+        assert_eq!("\
+noop      ; cycle 1, x 1
+addx 3    ; cycle 2, x 4
+addy 1    ; cycle 4, x 4
+jnz x 5   ; cycle 6, x 4
+addx 100
+noop      ; cycle 8, x 4
+", text);
+        assert_eq!(program, assemble(&text).unwrap());
+
+        //  An infinite loop doesn't stop the disassembly, even if it overflows X
+        let program = assemble("loop: jmp loop\nnoop").unwrap();
+        assert_eq!("jmp 0  ; cycle 1, x 1\nnoop\n", disassemble(&program, true));
+        let program = assemble("addx 1\nloop: mulx 3\njmp loop").unwrap();
+        assert_eq!("addx 1  ; cycle 1, x 2\nmulx 3  ; cycle 3, x 6\njmp 1   ; cycle 6, x 6\n", disassemble(&program, true));
+        assert_eq!("", disassemble(&[], true));
+    }
+}
@@ -1,5 +1,12 @@
+use std::fmt;
 use std::io::BufRead;
 
+use crate::bignum::BigUint;
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 11;
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Operator {
     Addition,
@@ -8,205 +15,758 @@ pub enum Operator {
     Division,
 }
 
+/// An expression giving a new worry level in terms of the old one
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    /// The old worry level
+    Old,
+    Literal(i64),
+    Binary(Box<Expr>, Operator, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluate the expression for the specified old worry level
+    pub fn eval(&self, old: i64) -> Result<i64, WorryError> {
+        match self {
+            Expr::Old => Ok(old),
+            Expr::Literal(v) => Ok(*v),
+            Expr::Binary(lhs, op, rhs) => do_op(lhs.eval(old)?, *op, rhs.eval(old)?),
+        }
+    }
+
+    /// Whether the expression divides anywhere
+    pub fn has_division(&self) -> bool {
+        match self {
+            Expr::Binary(lhs, op, rhs) => *op == Operator::Division || lhs.has_division() || rhs.has_division(),
+            _ => false,
+        }
+    }
+
+    /// Evaluate the expression with arbitrary precision.  Exact worry levels can't
+    /// be negative, and anything used as a divisor must fit in a u64.
+    pub fn eval_exact(&self, old: &BigUint) -> Result<BigUint, WorryError> {
+        match self {
+            Expr::Old => Ok(old.clone()),
+            Expr::Literal(v) => u64::try_from(*v).map(BigUint::from).map_err(|_| WorryError::Negative),
+            Expr::Binary(lhs, op, rhs) => {
+                let value = lhs.eval_exact(old)?;
+                let arg = rhs.eval_exact(old)?;
+                match op {
+                    Operator::Addition => Ok(value.add(&arg)),
+                    Operator::Subtraction => value.checked_sub(&arg).ok_or(WorryError::Negative),
+                    Operator::Multiplication => Ok(value.mul(&arg)),
+                    Operator::Division => match arg.to_u64() {
+                        Some(0) => Err(WorryError::DivisionByZero),
+                        Some(divisor) => Ok(value.div_rem_u64(divisor).0),
+                        None => Err(WorryError::Overflow),
+                    },
+                }
+            },
+        }
+    }
+}
+
+/// Why a worry level couldn't be calculated
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorryError {
+    /// The worry level got too big to represent
+    Overflow,
+    DivisionByZero,
+    /// The worry level went below zero, which exact worry levels can't represent
+    Negative,
+}
+
+impl fmt::Display for WorryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorryError::Overflow => write!(f, "worry level overflow"),
+            WorryError::DivisionByZero => write!(f, "division by zero"),
+            WorryError::Negative => write!(f, "negative worry level"),
+        }
+    }
+}
+
+/// An error encountered while the monkeys were playing
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonkeyError {
+    /// The LCM of the test divisors is too big to use as a modulus
+    ModulusOverflow,
+    /// A monkey's operation divides, so its worry levels can't be worked out modulo
+    /// anything
+    DivisionUnderModulus { monkey: usize },
+    /// A monkey inspected more items than can be counted
+    CountOverflow,
+    /// A monkey couldn't work out the new worry level for an item it inspected
+    Worry { round: i64, monkey: usize, error: WorryError },
+}
+
+impl fmt::Display for MonkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MonkeyError::ModulusOverflow => write!(f, "the LCM of the test divisors overflows"),
+            MonkeyError::DivisionUnderModulus { monkey } => {
+                write!(f, "monkey {monkey}'s operation divides, so worry levels can't be reduced by a modulus")
+            },
+            MonkeyError::CountOverflow => write!(f, "inspection count overflow"),
+            MonkeyError::Worry { round, monkey, error } => write!(f, "round {round}, monkey {monkey}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for MonkeyError {}
+
 #[derive(Clone)]
 pub struct Monkey {
     pub items: Vec<i64>,
-    items_inspected: i64,
-    worry_op: Operator,
-    worry_op_arg: i64,
+    items_inspected: u64,
+    worry_expr: Expr,
     test_divisor: i64,
     dst_true: usize,
     dst_false: usize,
 }
 
+impl Default for Monkey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Monkey {
     pub fn new() -> Self {
         Self {
             items: Vec::new(),
             items_inspected: 0,
-            worry_op: Operator::Addition,
-            worry_op_arg: 1,
+            worry_expr: Expr::Old,
             test_divisor: 1,
             dst_true: 0,
             dst_false: 0,
         }
     }
-}
 
-enum InputState {
-    MonkeyNum,
-    StartingItems,
-    Operation,
-    Test,
-    DstTrue,
-    DstFalse,
-    Blank,
+    /// The number of times this monkey has inspected an item
+    pub fn items_inspected(&self) -> u64 {
+        self.items_inspected
+    }
 }
 
-fn str2op(op_str: &str) -> Operator {
+fn str2op(op_str: &str) -> Option<Operator> {
     match op_str.trim() {
-        "+" => Operator::Addition,
-        "-" => Operator::Subtraction,
-        "*" => Operator::Multiplication,
-        "/" => Operator::Division,
-        _ => panic!("Invalid operator"),
+        "+" => Some(Operator::Addition),
+        "-" => Some(Operator::Subtraction),
+        "*" => Some(Operator::Multiplication),
+        "/" => Some(Operator::Division),
+        _ => None,
     }
 }
 
-fn do_op(value: i64, op: Operator, arg: i64) -> i64 {
-    let mut real_arg = arg;
+fn do_op(value: i64, op: Operator, arg: i64) -> Result<i64, WorryError> {
+    let result = match op {
+        Operator::Addition => value.checked_add(arg),
+        Operator::Subtraction => value.checked_sub(arg),
+        Operator::Multiplication => value.checked_mul(arg),
+        Operator::Division if arg == 0 => return Err(WorryError::DivisionByZero),
+        Operator::Division => value.checked_div(arg),
+    };
+    return result.ok_or(WorryError::Overflow);
+}
 
-    if arg == i64::MAX {
-        real_arg = value;
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+    return a;
+}
 
-    match op {
-        Operator::Addition => value + real_arg,
-        Operator::Subtraction => value - real_arg,
-        Operator::Multiplication => value * real_arg,
-        Operator::Division => value / real_arg,
+/// Find the least common multiple of all the test divisors, which is the smallest
+/// modulus that preserves every monkey's divisibility test.  Working modulo it is
+/// only sound for addition, subtraction and multiplication, so operations that
+/// divide are rejected.
+fn calc_modulus(monkeys: &[Monkey]) -> Result<i64, MonkeyError> {
+    if let Some(monkey) = monkeys.iter().position(|monkey| monkey.worry_expr.has_division()) {
+        return Err(MonkeyError::DivisionUnderModulus { monkey });
     }
-}
 
-//  Find a common multiple of all the test divisors.  Because they're all prime, there's no
-//  need to find the LCM.
-fn calc_divisor_multiple(monkeys: &Vec<Monkey>) -> i64 {
-    let mut m = 1;
+    let mut m: i64 = 1;
     for monkey in monkeys {
-        m = m * monkey.test_divisor;
+        let d = monkey.test_divisor.abs();
+        if d == 0 {
+            continue;
+        }
+        m = (m / gcd(m, d)).checked_mul(d).ok_or(MonkeyError::ModulusOverflow)?;
     }
-    return m;
+    return Ok(m);
 }
 
-fn play_round(monkeys: &mut Vec<Monkey>, worry_divisor: i64, p: i64) {
+/// Work out the new worry level for an item and where it gets thrown to
+fn inspect(monkey: &Monkey, worry: i64, worry_divisor: i64, modulus: Option<i64>) -> Result<(i64, usize), WorryError> {
+    //  I don't think I could have come up with this myself.
+    //  Using modulo arithmetic it is possible to divide the worry level
+    //  in such a way as to preserve the division tests required for the problem.
+    //  There's a great explanation here: https://www.reddit.com/r/adventofcode/comments/ziw4aq/comment/izsr5av
+    let reduce = |w: i64| modulus.map_or(w, |m| w.rem_euclid(m));
+
+    let new_worry_level = do_op(monkey.worry_expr.eval(reduce(worry))?, Operator::Division, worry_divisor)?;
+    let new_worry_level = reduce(new_worry_level);
+
+    //  Throw the item to the appropriate destination monkey, depending on whether
+    //  the new_worry_level is evenly divisible by the test number
+    let divisible = new_worry_level.checked_rem(monkey.test_divisor).ok_or(WorryError::DivisionByZero)? == 0;
+    let dst = if divisible { monkey.dst_true } else { monkey.dst_false };
+
+    return Ok((new_worry_level, dst));
+}
+
+/// Play a round, reducing worry levels by the modulus (if any) after every operation
+fn play_round(monkeys: &mut [Monkey], round: i64, worry_divisor: i64, modulus: Option<i64>) -> Result<(), MonkeyError> {
     //  Each monkey gets a turn
     for m in 0..monkeys.len() {
         for i in 0..monkeys[m].items.len() {
-            //  I don't think I could have come up with this myself.
-            //  Using modulo arithmetic it is possible to divide the worry level
-            //  in such a way as to preserve the division tests required for the problem.
-            //  There's a great explanation here: https://www.reddit.com/r/adventofcode/comments/ziw4aq/comment/izsr5av
-            let current_worry_level = monkeys[m].items[i] % p;
-            let new_worry_level = do_op(current_worry_level, monkeys[m].worry_op, monkeys[m].worry_op_arg) / worry_divisor;
-
-            //  Throw the item to the appropriate destination monkey, depending on whether
-            //  the new_worry_level is evenly divisible by the test number
-            //let mod_worry = new_worry_level % p;
-            if new_worry_level % monkeys[m].test_divisor == 0 {
-                let dst = monkeys[m].dst_true;
-                monkeys[dst].items.push(new_worry_level);
-            } else {
-                let dst = monkeys[m].dst_false;
-                monkeys[dst].items.push(new_worry_level);
-            }
-
+            let (new_worry_level, dst) = inspect(&monkeys[m], monkeys[m].items[i], worry_divisor, modulus)
+                .map_err(|error| MonkeyError::Worry { round, monkey: m, error })?;
+            monkeys[dst].items.push(new_worry_level);
             monkeys[m].items_inspected += 1;
         }
         monkeys[m].items.clear();
     }
+
+    return Ok(());
+}
+
+/// Play rounds where worry levels are divided by three after each inspection.  The
+/// division means a modulus can't be used, so the worry levels are exact and an
+/// error is returned if one no longer fits in an i64.
+pub fn play_rounds_p1(monkeys: &mut [Monkey], rounds: i64) -> Result<(), MonkeyError> {
+    for round in 1..=rounds {
+        play_round(monkeys, round, 3, None)?;
+    }
+    return Ok(());
 }
 
-pub fn play_rounds_p1(monkeys: &mut Vec<Monkey>, rounds: i64) {
-    let p = calc_divisor_multiple(monkeys);
-    for _ in 0..rounds {
-        play_round(monkeys, 3, p);
+/// Play rounds where worry levels are never divided, keeping them manageable by
+/// working modulo the LCM of the test divisors.  This is only sound for operations
+/// made of addition, subtraction and multiplication, so an error is returned if
+/// any operation divides.
+pub fn play_rounds_p2(monkeys: &mut [Monkey], rounds: i64) -> Result<(), MonkeyError> {
+    let modulus = calc_modulus(monkeys)?;
+    for round in 1..=rounds {
+        play_round(monkeys, round, 1, Some(modulus))?;
     }
+    return Ok(());
 }
 
-pub fn play_rounds_p2(monkeys: &mut Vec<Monkey>, rounds: i64) {
-    let p = calc_divisor_multiple(monkeys);
-    for _ in 0..rounds {
-        play_round(monkeys, 1, p);
+/// Play rounds with arbitrary precision worry levels, dividing them by
+/// `worry_divisor` after each inspection and never reducing them by a modulus.
+/// This is slow, and the worry levels can get very large without a divisor, but
+/// it is exact for any operation.  The monkeys' inspection counts are updated as
+/// usual, but because the worry levels may not fit in their items they are
+/// returned (indexed by monkey) instead, leaving the monkeys' items empty.
+pub fn play_rounds_exact(monkeys: &mut [Monkey], rounds: i64, worry_divisor: u64) -> Result<Vec<Vec<BigUint>>, MonkeyError> {
+    let mut items = Vec::new();
+    for (m, monkey) in monkeys.iter_mut().enumerate() {
+        let levels = monkey.items.drain(..).map(|w| u64::try_from(w).map(BigUint::from)).collect::<Result<Vec<_>, _>>();
+        items.push(levels.map_err(|_| MonkeyError::Worry { round: 0, monkey: m, error: WorryError::Negative })?);
+    }
+
+    for round in 1..=rounds {
+        for m in 0..monkeys.len() {
+            let error = |error| MonkeyError::Worry { round, monkey: m, error };
+            let divisor = Some(monkeys[m].test_divisor.unsigned_abs()).filter(|d| *d != 0 && worry_divisor != 0)
+                .ok_or_else(|| error(WorryError::DivisionByZero))?;
+
+            for worry in std::mem::take(&mut items[m]) {
+                let (new_worry_level, _) = monkeys[m].worry_expr.eval_exact(&worry).map_err(error)?.div_rem_u64(worry_divisor);
+                let dst = if new_worry_level.div_rem_u64(divisor).1 == 0 { monkeys[m].dst_true } else { monkeys[m].dst_false };
+                items[dst].push(new_worry_level);
+                monkeys[m].items_inspected += 1;
+            }
+        }
     }
+
+    return Ok(items);
 }
 
-pub fn calc_monkey_business(monkeys: &Vec<Monkey>) -> i64 {
-    let mut inspect_count_vector: Vec<i64> = Vec::new();
+/// Follow an item through a round in which worry levels aren't divided, from the
+/// monkey holding it at the start of the round until it's thrown to a monkey that
+/// has already had its turn.  Returns where the item ends up and adds its
+/// inspections to `inspected`.
+fn item_round(monkeys: &[Monkey], modulus: i64, round: i64, state: (usize, i64), inspected: &mut [u64]) -> Result<(usize, i64), MonkeyError> {
+    let (mut m, mut worry) = state;
+    loop {
+        let (new_worry_level, dst) = inspect(&monkeys[m], worry, 1, Some(modulus))
+            .map_err(|error| MonkeyError::Worry { round, monkey: m, error })?;
+        inspected[m] += 1;
+
+        if dst <= m {
+            return Ok((dst, new_worry_level));
+        }
+        (m, worry) = (dst, new_worry_level);
+    }
+}
+
+/// Follow an item through `count` rounds, the first of which is round `round`,
+/// adding its inspections to `inspected`.  Returns where the item ends up.
+fn item_rounds(monkeys: &[Monkey], modulus: i64, round: i64, count: i64, state: (usize, i64), inspected: &mut [u64]) -> Result<(usize, i64), MonkeyError> {
+    let mut state = state;
+    for r in round..round + count {
+        state = item_round(monkeys, modulus, r, state, inspected)?;
+    }
+    return Ok(state);
+}
+
+/// Work out where an item will be after the specified number of rounds, and how
+/// many times each monkey will have inspected it by then.  An item's state at the
+/// start of a round (the monkey holding it and its worry level modulo the LCM)
+/// decides everything that happens to it afterwards, and there are only so many
+/// states, so the item must eventually return to one it has been in before.  From
+/// then on it goes round the same cycle, so the remaining rounds can be skipped a
+/// whole cycle at a time.  The cycle is found with Brent's algorithm, so only a
+/// few states and count vectors are kept however long it takes to find.
+fn fast_forward_item(monkeys: &[Monkey], modulus: i64, start: (usize, i64), rounds: i64) -> Result<((usize, i64), Vec<u64>), MonkeyError> {
+    let mut inspected = vec![0u64; monkeys.len()];
+    if rounds <= 0 {
+        return Ok((start, inspected));
+    }
+
+    //  Find the length of the cycle.  The hare plays the rounds in order, so if
+    //  they run out before the cycle is found, it has the answer.
+    let (mut power, mut cycle_len) = (1, 1);
+    let mut tortoise = start;
+    let mut hare = item_round(monkeys, modulus, 1, start, &mut inspected)?;
+    let mut played = 1;
+    while tortoise != hare && played < rounds {
+        if power == cycle_len {
+            tortoise = hare;
+            power *= 2;
+            cycle_len = 0;
+        }
+        played += 1;
+        hare = item_round(monkeys, modulus, played, hare, &mut inspected)?;
+        cycle_len += 1;
+    }
+    if played == rounds {
+        return Ok((hare, inspected));
+    }
+
+    //  Find where the cycle starts, which is where the item meets itself cycle_len
+    //  rounds ahead, counting the inspections before it
+    let mut scratch = vec![0u64; monkeys.len()];
+    let mut prefix = vec![0u64; monkeys.len()];
+    let mut state = start;
+    let mut ahead = item_rounds(monkeys, modulus, 1, cycle_len, start, &mut scratch)?;
+    let mut cycle_start = 0;
+    while state != ahead {
+        cycle_start += 1;
+        state = item_round(monkeys, modulus, cycle_start, state, &mut prefix)?;
+        ahead = item_round(monkeys, modulus, cycle_start + cycle_len, ahead, &mut scratch)?;
+    }
+
+    //  Skip whole cycles, then play the rounds left over
+    let mut cycle = vec![0u64; monkeys.len()];
+    item_rounds(monkeys, modulus, cycle_start + 1, cycle_len, state, &mut cycle)?;
+    let cycles = ((rounds - cycle_start) / cycle_len) as u64;
+    let extra = (rounds - cycle_start) % cycle_len;
+    let mut remainder = vec![0u64; monkeys.len()];
+    let end = item_rounds(monkeys, modulus, cycle_start + 1, extra, state, &mut remainder)?;
+
+    let mut counts = Vec::new();
+    for ((prefix_count, cycle_count), remainder_count) in prefix.iter().zip(&cycle).zip(&remainder) {
+        let count = cycle_count.checked_mul(cycles)
+            .and_then(|c| c.checked_add(*prefix_count))
+            .and_then(|c| c.checked_add(*remainder_count))
+            .ok_or(MonkeyError::CountOverflow)?;
+        counts.push(count);
+    }
+
+    return Ok((end, counts));
+}
+
+/// Play the same rounds as `play_rounds_p2`, but follow each item on its own and
+/// skip over the cycles in its trajectory, so that the inspection counts for
+/// enormous numbers of rounds can be found without playing them all.  The items
+/// end up with the same monkeys and worry levels as they would have, though not
+/// necessarily in the same order.
+pub fn play_rounds_fast(monkeys: &mut [Monkey], rounds: i64) -> Result<(), MonkeyError> {
+    let modulus = calc_modulus(monkeys)?;
+    let mut items = vec![Vec::new(); monkeys.len()];
+    let mut inspected = monkeys.iter().map(|monkey| monkey.items_inspected).collect::<Vec<u64>>();
+
+    for m in 0..monkeys.len() {
+        for &worry in &monkeys[m].items {
+            let ((dst, new_worry_level), counts) = fast_forward_item(monkeys, modulus, (m, worry.rem_euclid(modulus)), rounds)?;
+            items[dst].push(new_worry_level);
+            for (total, count) in inspected.iter_mut().zip(counts) {
+                *total = total.checked_add(count).ok_or(MonkeyError::CountOverflow)?;
+            }
+        }
+    }
+
+    for ((monkey, items), inspected) in monkeys.iter_mut().zip(items).zip(inspected) {
+        monkey.items = items;
+        monkey.items_inspected = inspected;
+    }
+
+    return Ok(());
+}
+
+/// Multiply the inspection counts of the two most active monkeys.  If there are
+/// fewer than two monkeys the missing counts are taken to be 0.
+pub fn calc_monkey_business(monkeys: &[Monkey]) -> u128 {
+    let mut inspect_count_vector: Vec<u128> = Vec::new();
 
     for monkey in monkeys {
-        inspect_count_vector.push(monkey.items_inspected);
+        inspect_count_vector.push(monkey.items_inspected as u128);
     }
 
     inspect_count_vector.sort();
     inspect_count_vector.reverse();
+    inspect_count_vector.resize(inspect_count_vector.len().max(2), 0);
 
     return inspect_count_vector[0] * inspect_count_vector[1];
 
 }
 
-pub fn read_monkeys(reader: &mut dyn BufRead) -> Vec<Monkey> {
-    let mut input_state: InputState = InputState::MonkeyNum;
-    let mut monkeys: Vec<Monkey> = Vec::new();
-    let mut monkey: Monkey = Monkey::new();
+/// Return the token at the specified index, or an error describing what was expected
+fn token<'a>(line_num: usize, line: &str, tokens: &[&'a str], index: usize, what: &str) -> Result<&'a str, ParseError> {
+    match tokens.get(index) {
+        Some(t) => Ok(t),
+        None => Err(ParseError::at(DAY, line_num, line, line.trim(), &format!("missing {what}"))),
+    }
+}
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
-        let trimmed_line = line.trim();
+/// Parse a numeric token, or return an error describing what was expected
+fn number<T: std::str::FromStr>(line_num: usize, line: &str, token: &str, what: &str) -> Result<T, ParseError> {
+    token.parse::<T>().map_err(|_| ParseError::at(DAY, line_num, line, token, &format!("invalid {what}")))
+}
 
-        match input_state {
-            InputState::MonkeyNum => {
-                let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-                //let monkey_num_str = tokens[1].replace(":", "");
-                //let _monkey_num = monkey_num_str.parse::<i64>().unwrap();
+/// Split an expression into tokens: numbers, names, operators and parentheses.
+/// The tokens are slices of `line` so errors can point at them.
+fn tokenize<'a>(line_num: usize, line: &str, expr: &'a str) -> Result<Vec<&'a str>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.char_indices().peekable();
 
-                monkey.items.clear();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
 
-                input_state = InputState::StartingItems;
-            },
-            InputState::StartingItems => {
-                let tokens = trimmed_line.split(":").collect::<Vec<&str>>();
-                let item_csv = tokens[1];
-                let item_str_vec = item_csv.split(",").collect::<Vec<&str>>();
-                for item_str in item_str_vec {
-                    monkey.items.push(item_str.trim().parse::<i64>().unwrap());
+        let mut end = start + c.len_utf8();
+        if c.is_ascii_alphanumeric() {
+            while let Some((i, n)) = chars.peek() {
+                if !n.is_ascii_alphanumeric() {
+                    break;
                 }
+                end = i + n.len_utf8();
+                chars.next();
+            }
+        } else if !"+-*/()".contains(c) {
+            return Err(ParseError::at(DAY, line_num, line, &expr[start..end], "invalid operator"));
+        }
 
-                input_state = InputState::Operation;
-            },
-            InputState::Operation => {
-                let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-                let op_str = tokens[4];
-                let arg_str = tokens[5];
-                monkey.worry_op = str2op(op_str);
-
-                if arg_str == "old" {
-                    monkey.worry_op_arg = i64::MAX;
-                } else {
-                    monkey.worry_op_arg = tokens[5].parse::<i64>().unwrap();
-                }
+        tokens.push(&expr[start..end]);
+    }
 
-                input_state = InputState::Test;
-            },
-            InputState::Test => {
-                let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-                let test_val_str = tokens[3];
-                monkey.test_divisor = test_val_str.parse::<i64>().unwrap();
+    return Ok(tokens);
+}
 
-                input_state = InputState::DstTrue;
-            },
-            InputState::DstTrue => {
-                let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-                let dst_str = tokens[5];
-                monkey.dst_true = dst_str.parse::<usize>().unwrap();
+/// The deepest an expression can be nested, counting both parentheses and
+/// operators, so that neither parsing nor evaluating it can overflow the stack
+const MAX_NESTING: usize = 100;
 
-                input_state = InputState::DstFalse;
-            },
-            InputState::DstFalse => {
-                let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
-                let dst_str = tokens[5];
-                monkey.dst_false = dst_str.parse::<usize>().unwrap();
+/// A recursive descent parser for worry level expressions.  Multiplication and
+/// division bind more tightly than addition and subtraction, and operators of the
+/// same precedence are applied left to right.
+struct ExprParser<'a> {
+    line_num: usize,
+    line: &'a str,
+    tokens: Vec<&'a str>,
+    pos: usize,
+    /// How deeply nested the expression being parsed is so far
+    depth: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn error(&self, token: &str, message: &str) -> ParseError {
+        ParseError::at(DAY, self.line_num, self.line, token, message)
+    }
+
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    /// Go one level deeper into the expression, at an operator or an opening
+    /// parenthesis, unless it's already nested as deeply as it can be
+    fn nest(&mut self, token: &str) -> Result<(), ParseError> {
+        if self.depth == MAX_NESTING {
+            return Err(self.error(token, "nesting too deep"));
+        }
+        self.depth += 1;
+        return Ok(());
+    }
+
+    /// Parse a sequence of operands separated by the specified operators
+    fn binary(&mut self, operators: &str, operand: fn(&mut Self) -> Result<Expr, ParseError>) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = operand(self)?;
+
+        //  Each operator nests everything before it one level deeper
+        while let Some(t) = self.peek() {
+            if t.len() != 1 || !operators.contains(t) {
+                break;
+            }
+            self.nest(t)?;
+            self.pos += 1;
+
+            let op = str2op(t).unwrap();
+            expr = Expr::Binary(Box::new(expr), op, Box::new(operand(self)?));
+        }
+
+        self.depth = depth;
+        return Ok(expr);
+    }
 
-                input_state = InputState::Blank;
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        self.binary("+-", Self::term)
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        self.binary("*/", Self::factor)
+    }
+
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let t = match self.peek() {
+            Some(t) => t,
+            None => return Err(self.error(&self.line[self.line.len()..], "expected an operand")),
+        };
+        self.pos += 1;
+
+        if t == "old" {
+            return Ok(Expr::Old);
+        } else if t == "(" {
+            self.nest(t)?;
+            let expr = self.expr()?;
+            self.depth -= 1;
+            match self.peek() {
+                Some(")") => self.pos += 1,
+                Some(other) => return Err(self.error(other, "expected ')'")),
+                None => return Err(self.error(&self.line[self.line.len()..], "expected ')'")),
+            }
+            return Ok(expr);
+        } else if t.starts_with(|c: char| c.is_ascii_digit()) {
+            return Ok(Expr::Literal(number(self.line_num, self.line, t, "operand")?));
+        } else {
+            return Err(self.error(t, "expected an operand"));
+        }
+    }
+}
+
+/// Parse the expression from an Operation line, e.g. "Operation: new = old * (old + 3)"
+fn parse_operation(line_num: usize, line: &str) -> Result<Expr, ParseError> {
+    let trimmed_line = line.trim();
+    let expr = trimmed_line.strip_prefix("Operation:")
+        .and_then(|rest| rest.trim_start().strip_prefix("new"))
+        .and_then(|rest| rest.trim_start().strip_prefix('='))
+        .ok_or_else(|| ParseError::at(DAY, line_num, line, trimmed_line, "expected 'Operation: new = ...'"))?;
+
+    let tokens = tokenize(line_num, line, expr)?;
+    let mut parser = ExprParser { line_num, line, tokens, pos: 0, depth: 0 };
+    let result = parser.expr()?;
+
+    if let Some(t) = parser.peek() {
+        return Err(parser.error(t, "unexpected token"));
+    }
+
+    return Ok(result);
+}
+
+/// Where something was read from, so that problems found with it later can be
+/// reported against the right place
+struct Location {
+    line_num: usize,
+    column: usize,
+    text: String,
+}
+
+impl Location {
+    /// The location of `token`, which should be a slice of `line`
+    fn new(line_num: usize, line: &str, token: &str) -> Self {
+        let err = ParseError::at(DAY, line_num, line, token, "");
+        Self { line_num, column: err.column, text: err.text }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::new(DAY, self.line_num, self.column, &self.text, message)
+    }
+}
+
+/// The fields every monkey needs, as they're named in the input
+const FIELDS: [&str; 5] = ["Starting items", "Operation", "Test", "If true", "If false"];
+
+/// A monkey that is being read
+struct MonkeyInput {
+    /// The number the monkey was declared with, and where
+    number: usize,
+    header: Location,
+    monkey: Monkey,
+    /// Which of FIELDS have been read so far
+    seen: [bool; 5],
+    /// Where the destination monkeys came from, for if true and if false
+    dst_locations: [Option<Location>; 2],
+}
+
+/// Read the destination monkey from an "If true" or "If false" line
+fn read_dst(line_num: usize, line: &str) -> Result<(usize, Location), ParseError> {
+    let tokens = line.split_whitespace().collect::<Vec<&str>>();
+    let dst_str = token(line_num, line, &tokens, 5, "destination monkey")?;
+    let dst = number(line_num, line, dst_str, "destination monkey")?;
+    return Ok((dst, Location::new(line_num, line, dst_str)));
+}
+
+/// Read the monkeys' notes.  Monkeys are identified by the number in their
+/// "Monkey N:" header, so they can be listed in any order (but must be numbered
+/// from 0 without gaps), and their fields can come in any order too.  Blank lines
+/// are ignored.  Every monkey must have all of its fields, and must throw to other
+/// monkeys that exist, so there must be at least two monkeys.
+pub fn read_monkeys(reader: &mut dyn BufRead) -> Result<Vec<Monkey>, ParseError> {
+    let mut inputs: Vec<MonkeyInput> = Vec::new();
+    let mut line_count = 0;
+
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line_num = line_num + 1;
+        line_count = line_num;
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num, &e))?;
+        let trimmed_line = line.trim();
+
+        if trimmed_line.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = trimmed_line.strip_prefix("Monkey") {
+            let number_str = rest.strip_suffix(':')
+                .ok_or_else(|| ParseError::at(DAY, line_num, &line, trimmed_line, "expected 'Monkey N:'"))?
+                .trim();
+            let monkey_num: usize = number(line_num, &line, number_str, "monkey number")?;
+            if let Some(other) = inputs.iter().find(|input| input.number == monkey_num) {
+                let message = format!("monkey {monkey_num} was already declared on line {}", other.header.line_num);
+                return Err(ParseError::at(DAY, line_num, &line, number_str, &message));
+            }
+
+            inputs.push(MonkeyInput {
+                number: monkey_num,
+                header: Location::new(line_num, &line, number_str),
+                monkey: Monkey::new(),
+                seen: [false; 5],
+                dst_locations: [None, None],
+            });
+            continue;
+        }
+
+        let input = match inputs.last_mut() {
+            Some(input) => input,
+            None => return Err(ParseError::at(DAY, line_num, &line, trimmed_line, "expected a monkey header")),
+        };
+
+        let (name, value) = trimmed_line.split_once(':')
+            .ok_or_else(|| ParseError::at(DAY, line_num, &line, trimmed_line, "expected a field"))?;
+        let field = FIELDS.iter().position(|f| *f == name)
+            .ok_or_else(|| ParseError::at(DAY, line_num, &line, name, "unknown field"))?;
+        if input.seen[field] {
+            let message = format!("monkey {} already has a '{name}' field", input.number);
+            return Err(ParseError::at(DAY, line_num, &line, name, &message));
+        }
+        input.seen[field] = true;
 
-                monkeys.push(monkey.clone());
+        match field {
+            0 => {
+                if !value.trim().is_empty() {
+                    for item_str in value.split(',') {
+                        input.monkey.items.push(number(line_num, &line, item_str.trim(), "item")?);
+                    }
+                }
+            },
+            1 => input.monkey.worry_expr = parse_operation(line_num, &line)?,
+            2 => {
+                let tokens = trimmed_line.split_whitespace().collect::<Vec<&str>>();
+                let test_val_str = token(line_num, &line, &tokens, 3, "test divisor")?;
+                input.monkey.test_divisor = number(line_num, &line, test_val_str, "test divisor")?;
+                if input.monkey.test_divisor <= 0 {
+                    return Err(ParseError::at(DAY, line_num, &line, test_val_str, "test divisor must be positive"));
+                }
             },
-            InputState::Blank => {
-                input_state = InputState::MonkeyNum;
+            3 => {
+                let (dst, location) = read_dst(line_num, &line)?;
+                input.monkey.dst_true = dst;
+                input.dst_locations[0] = Some(location);
             },
+            _ => {
+                let (dst, location) = read_dst(line_num, &line)?;
+                input.monkey.dst_false = dst;
+                input.dst_locations[1] = Some(location);
+            },
+        }
+    }
+
+    for input in &inputs {
+        if let Some(missing) = input.seen.iter().position(|seen| !seen) {
+            return Err(input.header.error(&format!("monkey {} has no '{}' field", input.number, FIELDS[missing])));
+        }
+    }
+
+    match inputs.first() {
+        None => return Err(ParseError::new(DAY, line_count.max(1), 1, "", "expected at least two monkeys, found none")),
+        Some(input) if inputs.len() == 1 => {
+            return Err(input.header.error("expected at least two monkeys, since a monkey can't throw to itself"));
+        },
+        _ => (),
+    }
+
+    inputs.sort_by_key(|input| input.number);
+    for (i, input) in inputs.iter().enumerate() {
+        if input.number != i {
+            return Err(input.header.error(&format!("there is no monkey {i} (monkeys should be numbered from 0)")));
+        }
+    }
+
+    for input in &inputs {
+        let dsts = [input.monkey.dst_true, input.monkey.dst_false];
+        for (dst, location) in dsts.iter().zip(input.dst_locations.iter().flatten()) {
+            if *dst >= inputs.len() {
+                return Err(location.error(&format!("there is no monkey {dst}")));
+            }
+            if *dst == input.number {
+                return Err(location.error(&format!("monkey {dst} can't throw to itself")));
+            }
+        }
+    }
+
+    return Ok(inputs.into_iter().map(|input| input.monkey).collect());
+}
+
+/// Solution for day 11
+pub struct Day11 {
+    monkeys: Vec<Monkey>,
+}
+
+impl Solution for Day11 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { monkeys: read_monkeys(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut monkeys = self.monkeys.clone();
+        match play_rounds_p1(&mut monkeys, 20) {
+            Ok(()) => Box::new(calc_monkey_business(&monkeys)),
+            Err(e) => Box::new(e),
         }
     }
 
-    return monkeys;
+    fn part2(&self) -> Answer {
+        let mut monkeys = self.monkeys.clone();
+        match play_rounds_p2(&mut monkeys, 10000) {
+            Ok(()) => Box::new(calc_monkey_business(&monkeys)),
+            Err(e) => Box::new(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -219,7 +779,7 @@ mod tests {
         let op = Operator::Addition;
         let arg = 3;
         let result = do_op(val, op, arg);
-        assert_eq!(6, result);
+        assert_eq!(Ok(6), result);
     }
 
     #[test]
@@ -228,7 +788,7 @@ mod tests {
         let op = Operator::Subtraction;
         let arg = 3;
         let result = do_op(val, op, arg);
-        assert_eq!(0, result);
+        assert_eq!(Ok(0), result);
     }
 
     #[test]
@@ -237,7 +797,7 @@ mod tests {
         let op = Operator::Multiplication;
         let arg = 3;
         let result = do_op(val, op, arg);
-        assert_eq!(9, result);
+        assert_eq!(Ok(9), result);
     }
 
     #[test]
@@ -246,7 +806,7 @@ mod tests {
         let op = Operator::Division;
         let arg = 3;
         let result = do_op(val, op, arg);
-        assert_eq!(1, result);
+        assert_eq!(Ok(1), result);
     }
 
     #[test]
@@ -260,6 +820,12 @@ mod tests {
         assert_eq!(23, adjusted_worry);
     }
 
+    /// Read the monkeys from notes that are known to be valid
+    fn read(input: &str) -> Vec<Monkey> {
+        let mut buf = input.as_bytes();
+        return read_monkeys(&mut buf).unwrap();
+    }
+
     #[test]
     fn read_one_monkey() {
         let input = "Monkey 0:
@@ -267,15 +833,36 @@ mod tests {
             Operation: new = old * 19
             Test: divisible by 23
             If true: throw to monkey 2
-            If false: throw to monkey 3";
+            If false: throw to monkey 3
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 0
+            If false: throw to monkey 0
+
+            Monkey 2:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 0
+            If false: throw to monkey 0
+
+            Monkey 3:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 0
+            If false: throw to monkey 0";
         let mut buf = input.as_bytes();
-        let monkeys = read_monkeys(&mut buf);
-        assert_eq!(1, monkeys.len());
+        let monkeys = read_monkeys(&mut buf).unwrap();
+        assert_eq!(4, monkeys.len());
         assert_eq!(2, monkeys[0].items.len());
         assert_eq!(79, monkeys[0].items[0]);
         assert_eq!(98, monkeys[0].items[1]);
-        assert_eq!(Operator::Multiplication, monkeys[0].worry_op);
-        assert_eq!(19, monkeys[0].worry_op_arg);
+        assert_eq!(Expr::Binary(Box::new(Expr::Old), Operator::Multiplication, Box::new(Expr::Literal(19))),
+            monkeys[0].worry_expr);
         assert_eq!(23, monkeys[0].test_divisor);
         assert_eq!(2, monkeys[0].dst_true);
         assert_eq!(3, monkeys[0].dst_false);
@@ -295,15 +882,29 @@ mod tests {
             Operation: new = old + 6
             Test: divisible by 19
                 If true: throw to monkey 2
-                If false: throw to monkey 0";
+                If false: throw to monkey 0
+
+            Monkey 2:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 0
+            If false: throw to monkey 0
+
+            Monkey 3:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 0
+            If false: throw to monkey 0";
         let mut buf = input.as_bytes();
-        let monkeys = read_monkeys(&mut buf);
-        assert_eq!(2, monkeys.len());
+        let monkeys = read_monkeys(&mut buf).unwrap();
+        assert_eq!(4, monkeys.len());
         assert_eq!(2, monkeys[0].items.len());
         assert_eq!(79, monkeys[0].items[0]);
         assert_eq!(98, monkeys[0].items[1]);
-        assert_eq!(Operator::Multiplication, monkeys[0].worry_op);
-        assert_eq!(19, monkeys[0].worry_op_arg);
+        assert_eq!(Expr::Binary(Box::new(Expr::Old), Operator::Multiplication, Box::new(Expr::Literal(19))),
+            monkeys[0].worry_expr);
         assert_eq!(23, monkeys[0].test_divisor);
         assert_eq!(2, monkeys[0].dst_true);
         assert_eq!(3, monkeys[0].dst_false);
@@ -313,10 +914,446 @@ mod tests {
         assert_eq!(65, monkeys[1].items[1]);
         assert_eq!(75, monkeys[1].items[2]);
         assert_eq!(74, monkeys[1].items[3]);
-        assert_eq!(Operator::Addition, monkeys[1].worry_op);
-        assert_eq!(6, monkeys[1].worry_op_arg);
+        assert_eq!(Expr::Binary(Box::new(Expr::Old), Operator::Addition, Box::new(Expr::Literal(6))),
+            monkeys[1].worry_expr);
         assert_eq!(19, monkeys[1].test_divisor);
         assert_eq!(2, monkeys[1].dst_true);
         assert_eq!(0, monkeys[1].dst_false);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_invalid_monkey() {
+        let input = "Monkey 0:
+            Starting items: 79, 98
+            Operation: new = old ^ 19
+            Test: divisible by 23
+            If true: throw to monkey 2
+            If false: throw to monkey 3";
+        let mut buf = input.as_bytes();
+        let err = read_monkeys(&mut buf).err().unwrap();
+        assert_eq!(11, err.day);
+        assert_eq!(3, err.line);
+        assert_eq!(34, err.column);
+        assert_eq!("^", err.text);
+
+        let input = "Monkey 0:
+            Starting items: 79, 9x8";
+        let mut buf = input.as_bytes();
+        let err = read_monkeys(&mut buf).err().unwrap();
+        assert_eq!(2, err.line);
+        assert_eq!(33, err.column);
+        assert_eq!("9x8", err.text);
+
+        let input = "Monkey 0:
+            Starting items: 79, 98
+            Operation: new = old * 19
+            Test: divisible by 23
+            If true: throw to monkey";
+        let mut buf = input.as_bytes();
+        let err = read_monkeys(&mut buf).err().unwrap();
+        assert_eq!(5, err.line);
+        assert_eq!("If true: throw to monkey", err.text);
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        assert_eq!(Err(WorryError::Overflow), do_op(i64::MAX, Operator::Addition, 1));
+        assert_eq!(Err(WorryError::Overflow), do_op(i64::MIN, Operator::Subtraction, 1));
+        assert_eq!(Err(WorryError::Overflow), do_op(i64::MAX / 2 + 1, Operator::Multiplication, 2));
+        assert_eq!(Err(WorryError::Overflow), do_op(i64::MIN, Operator::Division, -1));
+        assert_eq!(Err(WorryError::DivisionByZero), do_op(1, Operator::Division, 0));
+        assert_eq!(Err(WorryError::Overflow), parse("old * old").unwrap().eval(1 << 32));
+    }
+
+    #[test]
+    fn modulus_is_lcm() {
+        assert_eq!(6, gcd(12, -18));
+        assert_eq!(5, gcd(0, 5));
+
+        let monkeys = read("Monkey 0:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 4
+            If true: throw to monkey 1
+            If false: throw to monkey 2
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 6
+            If true: throw to monkey 0
+            If false: throw to monkey 2
+
+            Monkey 2:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 9
+            If true: throw to monkey 0
+            If false: throw to monkey 1");
+        assert_eq!(Ok(36), calc_modulus(&monkeys));
+
+        let monkeys = read("Monkey 0:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 9223372036854775807
+            If true: throw to monkey 1
+            If false: throw to monkey 1
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 9223372036854775806
+            If true: throw to monkey 0
+            If false: throw to monkey 0");
+        assert_eq!(Err(MonkeyError::ModulusOverflow), calc_modulus(&monkeys));
+    }
+
+    #[test]
+    fn modulus_with_shared_factors() {
+        //  With divisors 4 and 6, working modulo their product (24) would be fine
+        //  too, but the LCM (12) is what keeps the worry levels smallest
+        let game = read("Monkey 0:
+            Starting items: 5
+            Operation: new = old * old + 1
+            Test: divisible by 4
+            If true: throw to monkey 1
+            If false: throw to monkey 1
+
+            Monkey 1:
+            Starting items: 7
+            Operation: new = old * 5
+            Test: divisible by 6
+            If true: throw to monkey 0
+            If false: throw to monkey 0");
+        let mut monkeys = game.clone();
+        play_rounds_p2(&mut monkeys, 10).unwrap();
+        assert!(monkeys.iter().flat_map(|m| m.items.iter()).all(|w| (0..12).contains(w)));
+
+        //  Reduced levels must agree with the exact ones modulo each divisor
+        let mut exact = game;
+        let levels = play_rounds_exact(&mut exact, 10, 1).unwrap();
+        for (m, monkey) in monkeys.iter().enumerate() {
+            let reduced = levels[m].iter().map(|w| w.div_rem_u64(12).1 as i64).collect::<Vec<i64>>();
+            assert_eq!(monkey.items, reduced);
+            assert_eq!(monkey.items_inspected, exact[m].items_inspected);
+        }
+    }
+
+    #[test]
+    fn division_under_modulus() {
+        let game = read("Monkey 0:
+            Starting items: 79, 98
+            Operation: new = old * 19
+            Test: divisible by 23
+            If true: throw to monkey 1
+            If false: throw to monkey 2
+
+            Monkey 1:
+            Starting items: 54, 65, 75
+            Operation: new = old / 2 + 5
+            Test: divisible by 19
+            If true: throw to monkey 2
+            If false: throw to monkey 0
+
+            Monkey 2:
+            Starting items: 79, 60, 97
+            Operation: new = old * old
+            Test: divisible by 13
+            If true: throw to monkey 0
+            If false: throw to monkey 1");
+
+        let mut monkeys = game.clone();
+        let err = play_rounds_p2(&mut monkeys, 30).err().unwrap();
+        assert_eq!(MonkeyError::DivisionUnderModulus { monkey: 1 }, err);
+        assert!(monkeys.iter().all(|m| m.items_inspected == 0));
+
+        let mut monkeys = game.clone();
+        assert_eq!(Err(MonkeyError::DivisionUnderModulus { monkey: 1 }), play_rounds_fast(&mut monkeys, 30));
+
+        //  Exact worry levels are fine
+        let mut monkeys = game;
+        play_rounds_exact(&mut monkeys, 8, 1).unwrap();
+        assert!(monkeys.iter().all(|m| m.items_inspected > 0));
+
+        assert!(parse("(old + 3) * (4 / old)").unwrap().has_division());
+        assert!(!parse("(old + 3) * (4 - old)").unwrap().has_division());
+    }
+
+    #[test]
+    fn worry_errors() {
+        let game = read("Monkey 0:
+            Starting items: 10
+            Operation: new = old * old
+            Test: divisible by 2
+            If true: throw to monkey 1
+            If false: throw to monkey 1
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old * old
+            Test: divisible by 3
+            If true: throw to monkey 0
+            If false: throw to monkey 0");
+        let mut monkeys = game.clone();
+        let err = play_rounds_p1(&mut monkeys, 20).err().unwrap();
+        assert_eq!(MonkeyError::Worry { round: 3, monkey: 1, error: WorryError::Overflow }, err);
+        assert_eq!("round 3, monkey 1: worry level overflow", err.to_string());
+
+        //  The same game is fine with exact worry levels
+        let mut monkeys = game;
+        let levels = play_rounds_exact(&mut monkeys, 4, 3).unwrap();
+        assert!(monkeys.iter().all(|m| m.items.is_empty()));
+        assert_eq!(1, levels[0].len());
+        assert_eq!(None, levels[0][0].to_u64());
+
+        let game = |operation: &str| read(&format!("Monkey 0:
+            Starting items: 3
+            Operation: new = {operation}
+            Test: divisible by 2
+            If true: throw to monkey 1
+            If false: throw to monkey 1
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 3
+            If true: throw to monkey 0
+            If false: throw to monkey 0"));
+
+        let mut monkeys = game("old - 5");
+        let err = play_rounds_exact(&mut monkeys, 1, 1).err().unwrap();
+        assert_eq!(MonkeyError::Worry { round: 1, monkey: 0, error: WorryError::Negative }, err);
+
+        let mut monkeys = game("old / (old - 3)");
+        let err = play_rounds_p1(&mut monkeys, 1).err().unwrap();
+        assert_eq!(MonkeyError::Worry { round: 1, monkey: 0, error: WorryError::DivisionByZero }, err);
+    }
+
+    #[test]
+    fn monkey_business() {
+        let mut monkeys = read("Monkey 0:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 2
+            If true: throw to monkey 1
+            If false: throw to monkey 2
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 3
+            If true: throw to monkey 0
+            If false: throw to monkey 2
+
+            Monkey 2:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 5
+            If true: throw to monkey 0
+            If false: throw to monkey 1");
+        monkeys[0].items_inspected = 4;
+        monkeys[1].items_inspected = 7;
+        monkeys[2].items_inspected = 5;
+        assert_eq!(35, calc_monkey_business(&monkeys));
+        assert_eq!(0, calc_monkey_business(&monkeys[..1]));
+        assert_eq!(0, calc_monkey_business(&[]));
+    }
+
+    #[test]
+    fn fast_forward() {
+        let game = read("Monkey 0:
+            Starting items: 1, 2
+            Operation: new = old * 3 + 1
+            Test: divisible by 5
+            If true: throw to monkey 1
+            If false: throw to monkey 2
+
+            Monkey 1:
+            Starting items: 4
+            Operation: new = old * old
+            Test: divisible by 2
+            If true: throw to monkey 2
+            If false: throw to monkey 0
+
+            Monkey 2:
+            Starting items:
+            Operation: new = old + 7
+            Test: divisible by 3
+            If true: throw to monkey 0
+            If false: throw to monkey 1");
+
+        for rounds in 0..100 {
+            let mut slow = game.clone();
+            play_rounds_p2(&mut slow, rounds).unwrap();
+            let mut fast = game.clone();
+            play_rounds_fast(&mut fast, rounds).unwrap();
+
+            let counts = |monkeys: &[Monkey]| monkeys.iter().map(|m| m.items_inspected).collect::<Vec<u64>>();
+            assert_eq!(counts(&slow), counts(&fast), "{rounds}");
+        }
+
+        //  Each item is inspected by both monkeys every round, so three items are
+        //  too many for the first monkey's count
+        let game = |items: &str| read(&format!("Monkey 0:
+            Starting items: {items}
+            Operation: new = old + 1
+            Test: divisible by 2
+            If true: throw to monkey 1
+            If false: throw to monkey 1
+
+            Monkey 1:
+            Starting items:
+            Operation: new = old
+            Test: divisible by 3
+            If true: throw to monkey 0
+            If false: throw to monkey 0"));
+        let mut monkeys = game("1, 2");
+        play_rounds_fast(&mut monkeys, i64::MAX).unwrap();
+        assert_eq!(vec![2 * i64::MAX as u64; 2], monkeys.iter().map(|m| m.items_inspected).collect::<Vec<u64>>());
+        let mut monkeys = game("1, 2, 3");
+        assert_eq!(Err(MonkeyError::CountOverflow), play_rounds_fast(&mut monkeys, i64::MAX));
+    }
+
+    #[test]
+    fn read_monkeys_in_any_order() {
+        //  Monkeys out of order, fields out of order, and missing or extra blank lines
+        let input = "
+
+            Monkey 1:
+            Starting items: 54, 65
+            Operation: new = old + 6
+            If false: throw to monkey 0
+            Test: divisible by 19
+            If true: throw to monkey 2
+            Monkey 2:
+            Starting items:
+            Operation: new = old * old
+            Test: divisible by 13
+            If true: throw to monkey 1
+            If false: throw to monkey 0
+
+
+
+            Monkey 0:
+            Starting items: 79
+            Operation: new = old * 19
+            Test: divisible by 23
+            If true: throw to monkey 2
+            If false: throw to monkey 1
+
+            ";
+        let mut buf = input.as_bytes();
+        let monkeys = read_monkeys(&mut buf).unwrap();
+        assert_eq!(3, monkeys.len());
+        assert_eq!(vec![79], monkeys[0].items);
+        assert_eq!((23, 2, 1), (monkeys[0].test_divisor, monkeys[0].dst_true, monkeys[0].dst_false));
+        assert_eq!(vec![54, 65], monkeys[1].items);
+        assert_eq!((19, 2, 0), (monkeys[1].test_divisor, monkeys[1].dst_true, monkeys[1].dst_false));
+        assert!(monkeys[2].items.is_empty());
+        assert_eq!((13, 1, 0), (monkeys[2].test_divisor, monkeys[2].dst_true, monkeys[2].dst_false));
+    }
+
+    #[test]
+    fn read_invalid_monkey_graph() {
+        let monkey = |header: &str, test: &str, dst_true: &str, dst_false: &str| format!("{header}
+            Starting items: 1
+            Operation: new = old
+            Test: divisible by {test}
+            If true: throw to monkey {dst_true}
+            If false: throw to monkey {dst_false}
+            ");
+
+        let errors = [
+            (monkey("Monkey 0:", "2", "1", "1") + &monkey("Monkey 1:", "3", "0", "2"),
+                (12, 39, "2", "there is no monkey 2")),
+            (monkey("Monkey 0:", "2", "1", "0") + &monkey("Monkey 1:", "3", "0", "0"),
+                (6, 39, "0", "monkey 0 can't throw to itself")),
+            (monkey("Monkey 0:", "2", "1", "1") + &monkey("Monkey 2:", "3", "0", "0"),
+                (7, 20, "2", "there is no monkey 1 (monkeys should be numbered from 0)")),
+            (monkey("Monkey 0:", "2", "1", "1") + &monkey("Monkey 0:", "3", "0", "0"),
+                (7, 20, "0", "monkey 0 was already declared on line 1")),
+            (monkey("Monkey 0:", "0", "1", "1"),
+                (4, 32, "0", "test divisor must be positive")),
+            (monkey("Monkey 0", "2", "1", "1"),
+                (1, 1, "Monkey 0", "expected 'Monkey N:'")),
+            (monkey("Monkey x:", "2", "1", "1"),
+                (1, 8, "x", "invalid monkey number")),
+            ("  Starting items: 1".to_string(),
+                (1, 3, "Starting items: 1", "expected a monkey header")),
+            ("Monkey 0:\n  Starting items: 1\n  Operation: new = old\n  Operation: new = old".to_string(),
+                (4, 3, "Operation", "monkey 0 already has a 'Operation' field")),
+            ("Monkey 0:\n  Starting items: 1\n  Colour: brown".to_string(),
+                (3, 3, "Colour", "unknown field")),
+            ("Monkey 0:\n  Starting items: 1\n  throw to monkey 1".to_string(),
+                (3, 3, "throw to monkey 1", "expected a field")),
+            ("Monkey 0:\n  Starting items: 1\n  Operation: new = old".to_string(),
+                (1, 8, "0", "monkey 0 has no 'Test' field")),
+            ("".to_string(),
+                (1, 1, "", "expected at least two monkeys, found none")),
+            ("\n\n  \n".to_string(),
+                (3, 1, "", "expected at least two monkeys, found none")),
+            (monkey("Monkey 0:", "2", "1", "1"),
+                (1, 8, "0", "expected at least two monkeys, since a monkey can't throw to itself")),
+        ];
+
+        for (input, expected) in errors {
+            let mut buf = input.as_bytes();
+            let err = read_monkeys(&mut buf).err().unwrap();
+            assert_eq!(expected, (err.line, err.column, err.text.as_str(), err.message.as_str()), "{input}");
+        }
+    }
+
+    /// Parse the expression on an Operation line
+    fn parse(expr: &str) -> Result<Expr, ParseError> {
+        parse_operation(1, &format!("  Operation: new = {expr}"))
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(Expr::Old, parse("old").unwrap());
+        assert_eq!(81, parse("old * old").unwrap().eval(9).unwrap());
+        assert_eq!(84, parse("old * old + 3").unwrap().eval(9).unwrap());
+        assert_eq!(18, parse("old * (old + 3)").unwrap().eval(3).unwrap());
+        assert_eq!(5, parse("old - 2 - 3").unwrap().eval(10).unwrap());
+        assert_eq!(2, parse("old / 2 / 5").unwrap().eval(20).unwrap());
+        assert_eq!(7, parse("1 + old*2").unwrap().eval(3).unwrap());
+        assert_eq!(42, parse("((old))*(6)").unwrap().eval(7).unwrap());
+        assert_eq!(Expr::Binary(
+            Box::new(Expr::Literal(2)),
+            Operator::Subtraction,
+            Box::new(Expr::Binary(Box::new(Expr::Old), Operator::Multiplication, Box::new(Expr::Literal(3))))),
+            parse("2 - old * 3").unwrap());
+    }
+
+    #[test]
+    fn expression_errors() {
+        let errors = [
+            ("old *", 25, "", "expected an operand"),
+            ("old * (old + 3", 34, "", "expected ')'"),
+            ("old old", 24, "old", "unexpected token"),
+            ("new + 1", 20, "new", "expected an operand"),
+            ("old ) 1", 24, ")", "unexpected token"),
+            ("old % 3", 24, "%", "invalid operator"),
+            ("old + 99999999999999999999", 26, "99999999999999999999", "invalid operand"),
+        ];
+
+        for (expr, column, text, message) in errors {
+            let err = parse(expr).err().unwrap();
+            assert_eq!((column, text, message), (err.column, err.text.as_str(), err.message.as_str()), "{expr}");
+        }
+
+        //  Deep nesting, with parentheses or long chains of operators, would
+        //  overflow the stack
+        let nested = format!("{}old{}", "(".repeat(MAX_NESTING), ")".repeat(MAX_NESTING));
+        assert_eq!(Expr::Old, parse(&nested).unwrap());
+        assert_eq!(MAX_NESTING as i64, parse(&format!("{}old", "1 + ".repeat(MAX_NESTING))).unwrap().eval(0).unwrap());
+        let err = parse(&format!("old + {}old{}", "(".repeat(100_000), ")".repeat(100_000))).err().unwrap();
+        assert_eq!((25 + MAX_NESTING, "(", "nesting too deep"), (err.column, err.text.as_str(), err.message.as_str()));
+        let err = parse(&format!("old{}", " * old".repeat(100_000))).err().unwrap();
+        assert_eq!((24 + 6 * MAX_NESTING, "*", "nesting too deep"), (err.column, err.text.as_str(), err.message.as_str()));
+
+        let err = parse_operation(4, "Operation: old * 2").err().unwrap();
+        assert_eq!((4, 1), (err.line, err.column));
+        assert_eq!("expected 'Operation: new = ...'", err.message);
+    }
+}
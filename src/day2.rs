@@ -1,5 +1,10 @@
 use std::io::BufRead;
 
+use crate::common::{self, ParseError};
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 2;
+
 /// The set of possible player moves in a Rock-Paper-Scissors game
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RPS {
@@ -66,40 +71,55 @@ fn get_outcome(symbol: &str) -> Option<Outcome> {
     }
 }
 
+/// Split a strategy line into its two symbols
+fn read_symbols(line_num: usize, line: &str) -> Result<(&str, &str), ParseError> {
+    let tokens = line.split_whitespace().collect::<Vec<&str>>();
+
+    if tokens.len() != 2 {
+        return Err(ParseError::at(DAY, line_num, line, line.trim(), "expected two symbols"));
+    }
+
+    return Ok((tokens[0], tokens[1]));
+}
+
 /// Read type-1 strategies from the provided buffer
-pub fn read_strategy1_vector(reader: &mut dyn BufRead) -> Vec<Strat1> {
+pub fn read_strategy1_vector(reader: &mut dyn BufRead) -> Result<Vec<Strat1>, ParseError> {
     let mut strat_vector: Vec<Strat1> = Vec::new();
 
-    for line_result in reader.lines() {
+    for (line_num, line_result) in reader.lines().enumerate() {
 
-        let line = line_result.unwrap();
-        let tokens = line.trim().split(" ").collect::<Vec<&str>>();
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
+        let (opp, own) = read_symbols(line_num + 1, &line)?;
         let strat: Strat1 = Strat1 {
-            opp_move: get_rps(tokens[0]).unwrap(),
-            own_move: get_rps(tokens[1]).unwrap(),
+            opp_move: get_rps(opp)
+                .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, opp, "invalid move"))?,
+            own_move: get_rps(own)
+                .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, own, "invalid move"))?,
         };
         strat_vector.push(strat);
     }
 
-    return strat_vector;
+    return Ok(strat_vector);
 }
 
 /// Read type-2 strategies from the provided buffer
-pub fn read_strategy2_vector(reader: &mut dyn BufRead) -> Vec<Strat2> {
+pub fn read_strategy2_vector(reader: &mut dyn BufRead) -> Result<Vec<Strat2>, ParseError> {
     let mut strat_vector: Vec<Strat2> = Vec::new();
 
-    for line_result in reader.lines() {
+    for (line_num, line_result) in reader.lines().enumerate() {
 
-        let line = line_result.unwrap();
-        let tokens = line.trim().split(" ").collect::<Vec<&str>>();
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
+        let (opp, outcome) = read_symbols(line_num + 1, &line)?;
         let strat: Strat2 = Strat2 {
-            opp_move: get_rps(tokens[0]).unwrap(),
-            outcome: get_outcome(tokens[1]).unwrap(),
+            opp_move: get_rps(opp)
+                .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, opp, "invalid move"))?,
+            outcome: get_outcome(outcome)
+                .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, outcome, "invalid outcome"))?,
         };
         strat_vector.push(strat);
     }
 
-    return strat_vector;
+    return Ok(strat_vector);
 }
 
 /// Given a type-1 strategy, determine the round outcome
@@ -133,14 +153,14 @@ fn round_move(strat: &Strat2) -> RPS {
 }
 
 /// Solve each round the RPS game given a vector of type-1 strategies
-pub fn solve_type1(strategy_vector: &Vec<Strat1>) -> Vec<RPSRound> {
+pub fn solve_type1(strategy_vector: &[Strat1]) -> Vec<RPSRound> {
     let mut round_vector: Vec<RPSRound> = Vec::new();
     for strat in strategy_vector {
         let outcome = round_outcome(strat);
         let round: RPSRound = RPSRound {
             opp_move: strat.opp_move,
             own_move: strat.own_move,
-            outcome:  outcome,
+            outcome,
         };
         round_vector.push(round);
     }
@@ -148,13 +168,13 @@ pub fn solve_type1(strategy_vector: &Vec<Strat1>) -> Vec<RPSRound> {
 }
 
 /// Solve each round in the RPS game given a vector of type-2 strategies
-pub fn solve_type2(strategy_vector: &Vec<Strat2>) -> Vec<RPSRound> {
+pub fn solve_type2(strategy_vector: &[Strat2]) -> Vec<RPSRound> {
     let mut round_vector: Vec<RPSRound> = Vec::new();
     for strat in strategy_vector {
         let own_move = round_move(strat);
         let round: RPSRound = RPSRound {
             opp_move: strat.opp_move,
-            own_move: own_move,
+            own_move,
             outcome:  strat.outcome,
         };
         round_vector.push(round);
@@ -186,7 +206,7 @@ fn round_score(round: &RPSRound) -> u32 {
 }
 
 /// Determine the score of an RPS game (a vector of rounds)
-pub fn total_score(round_vector: &Vec<RPSRound>) -> u32 {
+pub fn total_score(round_vector: &[RPSRound]) -> u32 {
     let mut total: u32 = 0;
     for round in round_vector {
         total += round_score(round);
@@ -194,6 +214,31 @@ pub fn total_score(round_vector: &Vec<RPSRound>) -> u32 {
     return total;
 }
 
+/// Solution for day 2
+pub struct Day2 {
+    strategy1_vector: Vec<Strat1>,
+    strategy2_vector: Vec<Strat2>,
+}
+
+impl Solution for Day2 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        //  The two parts interpret the same input differently
+        let input = common::read_input(DAY, reader)?;
+        Ok(Self {
+            strategy1_vector: read_strategy1_vector(&mut input.as_bytes())?,
+            strategy2_vector: read_strategy2_vector(&mut input.as_bytes())?,
+        })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(total_score(&solve_type1(&self.strategy1_vector)))
+    }
+
+    fn part2(&self) -> Answer {
+        Box::new(total_score(&solve_type2(&self.strategy2_vector)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -243,7 +288,7 @@ mod tests {
         let input =
             "A Y";
         let mut buf = input.as_bytes();
-        let strategy_vector = read_strategy1_vector(&mut buf);
+        let strategy_vector = read_strategy1_vector(&mut buf).unwrap();
         assert_eq!(1, strategy_vector.len());
         assert_eq!(RPS::Rock, strategy_vector[0].opp_move);
         assert_eq!(RPS::Paper, strategy_vector[0].own_move);
@@ -261,7 +306,7 @@ mod tests {
         let input =
             "A Y";
         let mut buf = input.as_bytes();
-        let strategy_vector = read_strategy2_vector(&mut buf);
+        let strategy_vector = read_strategy2_vector(&mut buf).unwrap();
         assert_eq!(1, strategy_vector.len());
         assert_eq!(RPS::Rock, strategy_vector[0].opp_move);
         assert_eq!(Outcome::Draw, strategy_vector[0].outcome);
@@ -273,4 +318,34 @@ mod tests {
         let score = total_score(&round_vector);
         assert_eq!(4, score);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn invalid_move() {
+        let input =
+            "A Y
+            Q X";
+        let mut buf = input.as_bytes();
+        let err = read_strategy1_vector(&mut buf).unwrap_err();
+        assert_eq!(2, err.line);
+        assert_eq!(13, err.column);
+        assert_eq!("Q", err.text);
+
+        let input =
+            "A W";
+        let mut buf = input.as_bytes();
+        let err = read_strategy2_vector(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!(3, err.column);
+        assert_eq!("W", err.text);
+    }
+
+    #[test]
+    fn missing_symbol() {
+        let input =
+            "A";
+        let mut buf = input.as_bytes();
+        let err = read_strategy1_vector(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!("A", err.text);
+    }
+}
@@ -1,16 +1,34 @@
+use std::fmt;
 use std::io::BufRead;
 
-/// Read lines from the input buffer and store them in a vector
-pub fn read_rucksack_vector(reader: &mut dyn BufRead) -> Vec<String> {
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 3;
+
+/// Read lines from the input buffer and store them in a vector.  Each rucksack
+/// must contain an even number of items, identified by the letters a-z and A-Z.
+pub fn read_rucksack_vector(reader: &mut dyn BufRead) -> Result<Vec<String>, ParseError> {
     let mut rucksack_vector: Vec<String> = Vec::new();
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let trimmed_line = line.trim();
+
+        if let Some((pos, c)) = trimmed_line.char_indices().find(|(_, c)| !c.is_ascii_alphabetic()) {
+            let bad_item = &trimmed_line[pos..pos + c.len_utf8()];
+            return Err(ParseError::at(DAY, line_num + 1, &line, bad_item, "invalid item"));
+        }
+
+        if trimmed_line.len() % 2 != 0 {
+            return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line,
+                "rucksack must contain an even number of items"));
+        }
+
         rucksack_vector.push(trimmed_line.to_string());
     }
 
-    return rucksack_vector;
+    return Ok(rucksack_vector);
 }
 
 /// Find the set of characters common to the provided (2) strings
@@ -62,7 +80,7 @@ fn char_priority(c: &char) -> u32 {
 }
 
 /// Solve the day 3 part 1 problems
-pub fn error_priority_sum(rucksack_vector: &Vec<String>) -> u32 {
+pub fn error_priority_sum(rucksack_vector: &[String]) -> u32 {
     let mut sum = 0;
     for rucksack in rucksack_vector {
         let n = rucksack.len(); // n=length of input string
@@ -74,7 +92,7 @@ pub fn error_priority_sum(rucksack_vector: &Vec<String>) -> u32 {
         let c = n / 2;              // c=length of compartment strings
         let comp1 = &rucksack[0..c];
         let comp2 = &rucksack[c..n];
-        let common_chars = find_common_chars2(&comp1, &comp2);
+        let common_chars = find_common_chars2(comp1, comp2);
         for c in common_chars {
             sum += char_priority(&c);
         }
@@ -82,13 +100,33 @@ pub fn error_priority_sum(rucksack_vector: &Vec<String>) -> u32 {
     return sum;
 }
 
+/// A reason the badges of the groups of elves can't be found
+#[derive(Clone, Debug, PartialEq)]
+pub enum BadgeError {
+    /// The rucksacks can't be split into groups of three
+    GroupSize { rucksacks: usize },
+    /// A group (counting from 0) doesn't have exactly one item type in common
+    Badge { group: usize, common: usize },
+}
+
+impl fmt::Display for BadgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BadgeError::GroupSize { rucksacks } => write!(f, "{rucksacks} rucksacks can't be split into groups of three"),
+            BadgeError::Badge { group, common } => write!(f, "group {group} has {common} item types in common, not 1"),
+        }
+    }
+}
+
+impl std::error::Error for BadgeError {}
+
 /// Solve the day 3 part 2 problem
-pub fn badge_priority_sum(rucksack_vector: &Vec<String>) -> u32 {
+pub fn badge_priority_sum(rucksack_vector: &[String]) -> Result<u32, BadgeError> {
     let mut sum = 0;
     let n = rucksack_vector.len();  // n=number of rucksacks/elves
 
     if n % 3 != 0 {
-        panic!("number of rucksacks/elves must be divisible by three");
+        return Err(BadgeError::GroupSize { rucksacks: n });
     }
 
     let m = n / 3;  // m=number of groups
@@ -102,13 +140,35 @@ pub fn badge_priority_sum(rucksack_vector: &Vec<String>) -> u32 {
         );
         
         if common_chars.len() != 1 {
-            panic!("unexpected number of common characters in group {i}");
+            return Err(BadgeError::Badge { group: i, common: common_chars.len() });
         }
 
         sum += char_priority(&common_chars[0]);
     }
 
-    return sum;
+    return Ok(sum);
+}
+
+/// Solution for day 3
+pub struct Day3 {
+    rucksack_vector: Vec<String>,
+}
+
+impl Solution for Day3 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { rucksack_vector: read_rucksack_vector(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(error_priority_sum(&self.rucksack_vector))
+    }
+
+    fn part2(&self) -> Answer {
+        match badge_priority_sum(&self.rucksack_vector) {
+            Ok(sum) => Box::new(sum),
+            Err(e) => Box::new(e),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -119,7 +179,7 @@ mod tests {
     fn error_priority_sum1() {
         let input = "vJrwpWtwJgWrhcsFMMfFFhFp";
         let mut buf = input.as_bytes();
-        let rucksack_vector = read_rucksack_vector(&mut buf);
+        let rucksack_vector = read_rucksack_vector(&mut buf).unwrap();
         assert_eq!(1, rucksack_vector.len());
 
         let sum = error_priority_sum(&rucksack_vector);
@@ -130,7 +190,7 @@ mod tests {
     fn error_priority_sum2() {
         let input = "jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL";
         let mut buf = input.as_bytes();
-        let rucksack_vector = read_rucksack_vector(&mut buf);
+        let rucksack_vector = read_rucksack_vector(&mut buf).unwrap();
         assert_eq!(1, rucksack_vector.len());
 
         let sum = error_priority_sum(&rucksack_vector);
@@ -142,7 +202,7 @@ mod tests {
         let input = "vJrwpWtwJgWrhcsFMMfFFhFp
             jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL";
         let mut buf = input.as_bytes();
-        let rucksack_vector = read_rucksack_vector(&mut buf);
+        let rucksack_vector = read_rucksack_vector(&mut buf).unwrap();
         assert_eq!(2, rucksack_vector.len());
 
         let sum = error_priority_sum(&rucksack_vector);
@@ -155,13 +215,28 @@ mod tests {
             jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
             PmmdzqPrVvPwwTWBwg";
         let mut buf = input.as_bytes();
-        let rucksack_vector = read_rucksack_vector(&mut buf);
+        let rucksack_vector = read_rucksack_vector(&mut buf).unwrap();
         assert_eq!(3, rucksack_vector.len());
 
-        let sum = badge_priority_sum(&rucksack_vector);
+        let sum = badge_priority_sum(&rucksack_vector).unwrap();
         assert_eq!(18, sum);
     }
 
+    #[test]
+    fn badge_errors() {
+        let rucksacks = ["abcdef", "abcghi"].map(String::from);
+        assert_eq!(Err(BadgeError::GroupSize { rucksacks: 2 }), badge_priority_sum(&rucksacks));
+        assert_eq!("2 rucksacks can't be split into groups of three", BadgeError::GroupSize { rucksacks: 2 }.to_string());
+
+        let rucksacks = ["aAbB", "aAcC", "aAdD"].map(String::from);
+        assert_eq!(Err(BadgeError::Badge { group: 0, common: 2 }), badge_priority_sum(&rucksacks));
+
+        let rucksacks = ["aAbB", "aCcD", "aEdF", "xXyY", "zZwW", "vVuU"].map(String::from);
+        assert_eq!(Err(BadgeError::Badge { group: 1, common: 0 }), badge_priority_sum(&rucksacks));
+        assert_eq!(Ok(1), badge_priority_sum(&rucksacks[..3]));
+        assert_eq!(Ok(0), badge_priority_sum(&[]));
+    }
+
     #[test]
     fn common_chars2_1() {
         let s1 = "abcd";
@@ -212,4 +287,24 @@ mod tests {
         assert_eq!(52, char_priority(&'Z'));
     }
 
+    #[test]
+    fn invalid_item() {
+        let input = "vJrwpWtwJgWrhcsFMMfFFhFp
+            jqHRNqRjqz1GDLGLrsFMfFZSrLrFZsSL";
+        let mut buf = input.as_bytes();
+        let err = read_rucksack_vector(&mut buf).unwrap_err();
+        assert_eq!(2, err.line);
+        assert_eq!(23, err.column);
+        assert_eq!("1", err.text);
+    }
+
+    #[test]
+    fn odd_rucksack() {
+        let input = "abc";
+        let mut buf = input.as_bytes();
+        let err = read_rucksack_vector(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!(1, err.column);
+        assert_eq!("abc", err.text);
+    }
 }
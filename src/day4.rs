@@ -1,17 +1,23 @@
 use std::io::BufRead;
 
-/// Read a string of the form "x-y" and return a tuple (x,y)
-pub fn parse_sequence(s: &str) -> (u32,u32) {
-    let bounds = s.split("-").collect::<Vec<&str>>();
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 4;
+
+/// Read a string of the form "x-y" and return a tuple (x,y), or None if the
+/// string isn't a valid sequence
+pub fn parse_sequence(s: &str) -> Option<(u32,u32)> {
+    let bounds = s.split('-').collect::<Vec<&str>>();
 
     if bounds.len() != 2 {
-        panic!("invalid set input: {s}");
+        return None;
     }
 
-    let start = bounds[0].parse::<u32>().unwrap();
-    let end   = bounds[1].parse::<u32>().unwrap();
+    let start = bounds[0].trim().parse::<u32>().ok()?;
+    let end   = bounds[1].trim().parse::<u32>().ok()?;
 
-    return (start, end);
+    return Some((start, end));
 }
 
 #[derive(Debug,PartialEq)]
@@ -21,25 +27,27 @@ pub struct SequencePair {
 }
 
 /// Read pairs of sequences from the input buffer
-pub fn read_sequence_pairs(reader: &mut dyn BufRead) -> Vec<SequencePair> {
+pub fn read_sequence_pairs(reader: &mut dyn BufRead) -> Result<Vec<SequencePair>, ParseError> {
     let mut seq_pair_vector: Vec<SequencePair> = Vec::new();
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let trimmed_line = line.trim();
-        let sequences = trimmed_line.split(",").collect::<Vec<&str>>();
+        let sequences = trimmed_line.split(',').collect::<Vec<&str>>();
         
         if sequences.len() != 2 {
-            panic!("invalid set pair input: {trimmed_line}");
+            return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "invalid sequence pair"));
         }
 
-        let s1 = parse_sequence(sequences[0]);
-        let s2 = parse_sequence(sequences[1]);
+        let s1 = parse_sequence(sequences[0])
+            .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, sequences[0], "invalid sequence"))?;
+        let s2 = parse_sequence(sequences[1])
+            .ok_or_else(|| ParseError::at(DAY, line_num + 1, &line, sequences[1], "invalid sequence"))?;
         let pair: SequencePair = SequencePair { s1, s2 };
         seq_pair_vector.push(pair);
     }
 
-    return seq_pair_vector;
+    return Ok(seq_pair_vector);
 }
 
 /// Test whether one of the specified sequences contains the other
@@ -54,7 +62,7 @@ fn sequence_overlaps(s1: (u32,u32), s2: (u32,u32)) -> bool {
 
 /// Count the number of sequence pairs with containment
 /// (where one sequence in the pair contains the other)
-pub fn count_contained_pairs(seq_pair_vector: &Vec<SequencePair>) -> u32 {
+pub fn count_contained_pairs(seq_pair_vector: &[SequencePair]) -> u32 {
     let mut count = 0;
 
     for pair in seq_pair_vector {
@@ -68,7 +76,7 @@ pub fn count_contained_pairs(seq_pair_vector: &Vec<SequencePair>) -> u32 {
 
 /// Count the number of sequence pairs with overlap
 /// (where one sequence in the pair overlaps the other)
-pub fn count_overlapped_pairs(seq_pair_vector: &Vec<SequencePair>) -> u32 {
+pub fn count_overlapped_pairs(seq_pair_vector: &[SequencePair]) -> u32 {
     let mut count = 0;
 
     for pair in seq_pair_vector {
@@ -80,6 +88,25 @@ pub fn count_overlapped_pairs(seq_pair_vector: &Vec<SequencePair>) -> u32 {
     return count;
 }
 
+/// Solution for day 4
+pub struct Day4 {
+    seq_pair_vector: Vec<SequencePair>,
+}
+
+impl Solution for Day4 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { seq_pair_vector: read_sequence_pairs(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(count_contained_pairs(&self.seq_pair_vector))
+    }
+
+    fn part2(&self) -> Answer {
+        Box::new(count_overlapped_pairs(&self.seq_pair_vector))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -88,21 +115,28 @@ mod tests {
     fn parse_sequence_test1() {
         let input = "2-4";
         let seq = parse_sequence(input);
-        assert_eq!((2,4), seq);
+        assert_eq!(Some((2,4)), seq);
     }
 
     #[test]
     fn parse_sequence_test2() {
         let input = "1000-2000";
         let seq = parse_sequence(input);
-        assert_eq!((1000,2000), seq);
+        assert_eq!(Some((1000,2000)), seq);
+    }
+
+    #[test]
+    fn parse_sequence_invalid() {
+        assert_eq!(None, parse_sequence("2"));
+        assert_eq!(None, parse_sequence("2-x"));
+        assert_eq!(None, parse_sequence("1-2-3"));
     }
 
     #[test]
     fn read_sequence_pairs_test1() {
         let input = "2-4,6-8";
         let mut buf = input.as_bytes();
-        let sp = read_sequence_pairs(&mut buf);
+        let sp = read_sequence_pairs(&mut buf).unwrap();
         assert_eq!(1, sp.len());
         assert_eq!(SequencePair {s1: (2,4), s2: (6,8)}, sp[0]);
     }
@@ -112,12 +146,30 @@ mod tests {
         let input = "2-4,6-8
             2-3,4-5";
         let mut buf = input.as_bytes();
-        let sp = read_sequence_pairs(&mut buf);
+        let sp = read_sequence_pairs(&mut buf).unwrap();
         assert_eq!(2, sp.len());
         assert_eq!(SequencePair {s1: (2,4), s2: (6,8)}, sp[0]);
         assert_eq!(SequencePair {s1: (2,3), s2: (4,5)}, sp[1]);
     }
 
+    #[test]
+    fn read_sequence_pairs_invalid() {
+        let input = "2-4,6-8
+            2-3,4+5";
+        let mut buf = input.as_bytes();
+        let err = read_sequence_pairs(&mut buf).unwrap_err();
+        assert_eq!(4, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(17, err.column);
+        assert_eq!("4+5", err.text);
+
+        let input = "2-4";
+        let mut buf = input.as_bytes();
+        let err = read_sequence_pairs(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!("2-4", err.text);
+    }
+
     #[test]
     fn sequence_contains_test() {
         assert!( sequence_contains((2,8), (3,7)));
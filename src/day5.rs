@@ -1,5 +1,10 @@
 use std::{io::BufRead, collections::VecDeque};
 
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 5;
+
 #[derive(Debug,PartialEq)]
 pub struct StackMove {
     src: usize,
@@ -7,17 +12,25 @@ pub struct StackMove {
     count: usize,
 }
 
+/// Read a stack number from a move line, converting it to a stack index
+fn read_stack_index(line_num: usize, line: &str, token: &str, stack_count: usize) -> Result<usize, ParseError> {
+    match token.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= stack_count => Ok(n - 1),
+        _ => Err(ParseError::at(DAY, line_num, line, token, "invalid stack number")),
+    }
+}
+
 /// Read initial stack configuration and vector of moves from the input
-pub fn read_stacks_and_procedure(reader: &mut dyn BufRead) -> (Vec<VecDeque<char>>, Vec<StackMove>) {
+pub fn read_stacks_and_procedure(reader: &mut dyn BufRead) -> Result<(Vec<VecDeque<char>>, Vec<StackMove>), ParseError> {
     let mut stack_vector: Vec<VecDeque<char>> = Vec::new();
     let mut move_vector: Vec<StackMove> = Vec::new();
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let n = line.len(); // n=length of the line, including spaces
         let s = (n/4)+1;    // s=number of stacks
 
-        if stack_vector.len() == 0 {
+        if stack_vector.is_empty() {
             // Initialize stacks
             for _i in 0..s {
                 let stack: VecDeque<char> = VecDeque::new();
@@ -29,30 +42,39 @@ pub fn read_stacks_and_procedure(reader: &mut dyn BufRead) -> (Vec<VecDeque<char
         if line.contains('[') {
             // All stack lines should contain at least one bracket
 
-            for i in 0..s {
-                let c = line.chars().nth(1 + (i * 4)).unwrap();
-                if ('A'..='Z').contains(&c) {
+            for (i, c) in line.chars().skip(1).step_by(4).enumerate() {
+                if c.is_ascii_uppercase() {
+                    if i >= stack_vector.len() {
+                        let col = 1 + (i * 4);
+                        return Err(ParseError::at(DAY, line_num + 1, &line, &line[col..col + 1],
+                            "crate is outside the stacks"));
+                    }
                     stack_vector[i].push_front(c);
                 }
             }
         } else if line.contains("move") {
             // All procedure lines should contain the word move
 
-            let tok = line.split(" ").collect::<Vec<&str>>();
+            let tok = line.split_whitespace().collect::<Vec<&str>>();
+            if tok.len() != 6 || tok[0] != "move" || tok[2] != "from" || tok[4] != "to" {
+                return Err(ParseError::at(DAY, line_num + 1, &line, line.trim(), "invalid move"));
+            }
+
             let sm: StackMove = StackMove {
-                src: tok[3].parse::<usize>().unwrap() - 1,
-                dst: tok[5].parse::<usize>().unwrap() - 1,
-                count:     tok[1].parse::<usize>().unwrap(),
+                src: read_stack_index(line_num + 1, &line, tok[3], stack_vector.len())?,
+                dst: read_stack_index(line_num + 1, &line, tok[5], stack_vector.len())?,
+                count:     tok[1].parse::<usize>()
+                    .map_err(|_| ParseError::at(DAY, line_num + 1, &line, tok[1], "invalid crate count"))?,
             };
             move_vector.push(sm);
         }
     }
 
-    return (stack_vector, move_vector);
+    return Ok((stack_vector, move_vector));
 }
 
 /// Process a move using the part 1 method
-fn process_move(stacks: &mut Vec<VecDeque<char>>, m: &StackMove) {
+fn process_move(stacks: &mut [VecDeque<char>], m: &StackMove) {
     for _c in 0..m.count {
         let tmp = stacks[m.src].pop_back().unwrap();
         stacks[m.dst].push_back(tmp);
@@ -60,7 +82,7 @@ fn process_move(stacks: &mut Vec<VecDeque<char>>, m: &StackMove) {
 }
 
 /// Process a move using the part 2 method
-fn process_move2(stacks: &mut Vec<VecDeque<char>>, m: &StackMove) {
+fn process_move2(stacks: &mut [VecDeque<char>], m: &StackMove) {
     let mut tmpvec: VecDeque<char> = VecDeque::new();
 
     for _c in 0..m.count {
@@ -74,21 +96,21 @@ fn process_move2(stacks: &mut Vec<VecDeque<char>>, m: &StackMove) {
 }
 
 /// Process all moves using the part 1 method
-pub fn process_moves(stacks: &mut Vec<VecDeque<char>>, moves: &Vec<StackMove>) {
+pub fn process_moves(stacks: &mut [VecDeque<char>], moves: &[StackMove]) {
     for m in moves {
-        process_move(stacks, &m);
+        process_move(stacks, m);
     }
 }
 
 /// Process all moves using the part 2 method
-pub fn process_moves2(stacks: &mut Vec<VecDeque<char>>, moves: &Vec<StackMove>) {
+pub fn process_moves2(stacks: &mut [VecDeque<char>], moves: &[StackMove]) {
     for m in moves {
-        process_move2(stacks, &m);
+        process_move2(stacks, m);
     }
 }
 
 /// Make a string from the characters at the top of each stack
-pub fn top_crate_str(stacks: &Vec<VecDeque<char>>) -> String {
+pub fn top_crate_str(stacks: &[VecDeque<char>]) -> String {
     let mut s: String = String::from("");
 
     for stack in stacks {
@@ -97,6 +119,31 @@ pub fn top_crate_str(stacks: &Vec<VecDeque<char>>) -> String {
     return s;
 }
 
+/// Solution for day 5
+pub struct Day5 {
+    stacks: Vec<VecDeque<char>>,
+    moves: Vec<StackMove>,
+}
+
+impl Solution for Day5 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        let (stacks, moves) = read_stacks_and_procedure(reader)?;
+        Ok(Self { stacks, moves })
+    }
+
+    fn part1(&self) -> Answer {
+        let mut stacks = self.stacks.clone();
+        process_moves(&mut stacks, &self.moves);
+        Box::new(top_crate_str(&stacks))
+    }
+
+    fn part2(&self) -> Answer {
+        let mut stacks = self.stacks.clone();
+        process_moves2(&mut stacks, &self.moves);
+        Box::new(top_crate_str(&stacks))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,7 +160,7 @@ mod tests {
                             "move 2 from 2 to 1\n",
                             "move 1 from 1 to 2\n");
         let mut buf = input.as_bytes();
-        let (mut stacks, moves) = read_stacks_and_procedure(&mut buf);
+        let (mut stacks, moves) = read_stacks_and_procedure(&mut buf).unwrap();
         assert_eq!(3, stacks.len());
 
         assert_eq!(2, stacks[0].len());
@@ -190,7 +237,7 @@ mod tests {
                             "move 2 from 2 to 1\n",
                             "move 1 from 1 to 2\n");
         let mut buf = input.as_bytes();
-        let (mut stacks, moves) = read_stacks_and_procedure(&mut buf);
+        let (mut stacks, moves) = read_stacks_and_procedure(&mut buf).unwrap();
         assert_eq!(3, stacks.len());
 
         assert_eq!(2, stacks[0].len());
@@ -254,4 +301,26 @@ mod tests {
         assert_eq!('P', stacks[2][0]);
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_stacks_and_procedure_invalid() {
+        let input = concat!("[Z] [M] [P]\n",
+                            " 1   2   3 \n",
+                            "\n",
+                            "move 1 from 2 to 1\n",
+                            "move 3 from 1 to 4\n");
+        let mut buf = input.as_bytes();
+        let err = read_stacks_and_procedure(&mut buf).unwrap_err();
+        assert_eq!(5, err.line);
+        assert_eq!(18, err.column);
+        assert_eq!("4", err.text);
+
+        let input = concat!("[Z] [M] [P]\n",
+                            "move x from 1 to 2\n");
+        let mut buf = input.as_bytes();
+        let err = read_stacks_and_procedure(&mut buf).unwrap_err();
+        assert_eq!(2, err.line);
+        assert_eq!(6, err.column);
+        assert_eq!("x", err.text);
+    }
+}
@@ -1,3 +1,22 @@
+use std::io::BufRead;
+
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 6;
+
+/// Read the datastream buffer (the first line of the input)
+pub fn read_datastream(reader: &mut dyn BufRead) -> Result<String, ParseError> {
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|e| ParseError::io(DAY, 1, &e))?;
+
+    let trimmed_line = line.trim();
+    if trimmed_line.is_empty() {
+        return Err(ParseError::new(DAY, 1, 1, "", "empty datastream"));
+    }
+
+    return Ok(trimmed_line.to_string());
+}
 
 /// Find the end of the start of packet (or start of message) indicator
 /// by returning the number of characters that need to be processed before
@@ -33,6 +52,25 @@ pub fn find_sop(s: &str, d: usize) -> usize {
     return 0;
 }
 
+/// Solution for day 6
+pub struct Day6 {
+    datastream: String,
+}
+
+impl Solution for Day6 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { datastream: read_datastream(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(find_sop(&self.datastream, 4))
+    }
+
+    fn part2(&self) -> Answer {
+        Box::new(find_sop(&self.datastream, 14))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,4 +109,14 @@ mod tests {
         assert_eq!(11, find_sop(input, 4));
         assert_eq!(26, find_sop(input, 14));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_datastream_test() {
+        let input = "mjqjpqmgbljsphdztnvjfqwrcgsmlb\n";
+        let mut buf = input.as_bytes();
+        assert_eq!("mjqjpqmgbljsphdztnvjfqwrcgsmlb", read_datastream(&mut buf).unwrap());
+
+        let mut buf = "".as_bytes();
+        assert!(read_datastream(&mut buf).is_err());
+    }
+}
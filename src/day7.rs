@@ -1,170 +1,1078 @@
-use std::{io::BufRead, rc::Rc, cell::RefCell};
+use std::io::BufRead;
+use std::ops::{Bound, RangeBounds};
 
-#[derive(PartialEq)]
+use crate::common::{self, ParseError};
+use crate::glob::Glob;
+use crate::json::{self, Json, JsonValue};
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 7;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NodeType {
     File,
     Directory,
 }
 
+/// A handle to a node in a FileSystem
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Debug)]
 pub struct TreeNode {
-    node_type: NodeType,
-    children: Vec<Rc<RefCell<TreeNode>>>,
-    parent: Option<Rc<RefCell<TreeNode>>>,
-    name: String,
+    pub node_type: NodeType,
+    children: Vec<NodeId>,
+    parent: Option<NodeId>,
+    pub name: String,
     pub size: usize,
+    /// Whether the node has been removed from the filesystem.  Removed nodes stay
+    /// in the arena so that ids are never reused, but can't be accessed.
+    removed: bool,
 }
 
-/// Update the sizes of the provided node and its children.  Why didn't I use my 
-/// dfs implementation for this?
-pub fn update_sizes(node: Rc<RefCell<TreeNode>>) -> usize {
-    let mut rm = node.borrow_mut();
-    match rm.node_type {
-        NodeType::File => {
-            return rm.size;
-        },
-        NodeType::Directory => {
-            let mut sz: usize = 0;
-            for n in &rm.children {
-                sz += update_sizes(n.clone());
+/// A filesystem tree.  The nodes are owned by the FileSystem (an arena) and refer
+/// to each other by NodeId, so there are no reference cycles to leak or borrows
+/// to juggle while walking the tree.
+#[derive(Debug)]
+pub struct FileSystem {
+    nodes: Vec<TreeNode>,
+}
+
+impl Default for FileSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Two filesystems are equal if their trees have the same shape, with the same
+/// names, types and sizes, regardless of how the nodes are laid out in the arena
+impl PartialEq for FileSystem {
+    fn eq(&self, other: &Self) -> bool {
+        let mut pairs = vec![(self.root(), other.root())];
+        while let Some((a, b)) = pairs.pop() {
+            let (node_a, node_b) = (self.node(a), other.node(b));
+            if node_a.node_type != node_b.node_type || node_a.name != node_b.name || node_a.size != node_b.size
+                || node_a.children.len() != node_b.children.len() {
+                return false;
             }
-            rm.size = sz;
-            return sz;
-        },
+            pairs.extend(node_a.children.iter().copied().zip(node_b.children.iter().copied()));
+        }
+
+        return true;
     }
 }
 
-/// Perform a depth-first search of the provided graph (tree).  The callback function
-/// will be called for each node visited, to support arbitrary operations based on the 
-/// graph structure.
-pub fn dfs(node: Rc<RefCell<TreeNode>>, callback: &mut dyn FnMut(Rc<RefCell<TreeNode>>)) {
-    callback(node.clone());
+impl FileSystem {
+    /// Create a new filesystem containing only the root directory
+    pub fn new() -> Self {
+        let root = TreeNode {
+            node_type: NodeType::Directory,
+            children: Vec::new(),
+            parent: None,
+            name: "/".to_string(),
+            size: 0,
+            removed: false,
+        };
+        Self { nodes: vec![root] }
+    }
+
+    /// Return the root directory
+    pub fn root(&self) -> NodeId {
+        NodeId(0)
+    }
+
+    /// Whether a node is in the filesystem, i.e. it hasn't been removed
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.get(id).is_some()
+    }
+
+    /// Return the node with the specified id, or None if it has been removed
+    pub fn get(&self, id: NodeId) -> Option<&TreeNode> {
+        self.nodes.get(id.0).filter(|node| !node.removed)
+    }
+
+    /// Return the node with the specified id.  Panics if the node has been removed.
+    pub fn node(&self, id: NodeId) -> &TreeNode {
+        match self.get(id) {
+            Some(node) => node,
+            None => panic!("node {} has been removed", id.0),
+        }
+    }
+
+    /// Return the parent directory of a node (None for the root directory)
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.node(id).parent
+    }
+
+    /// Return the children of a node, in the order they were added
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        &self.node(id).children
+    }
+
+    /// Return an error if a node has been removed, for operations that change the
+    /// filesystem
+    fn check(&self, id: NodeId) -> Result<&TreeNode, String> {
+        self.get(id).ok_or_else(|| format!("node {} has been removed", id.0))
+    }
+
+    /// Find the child of a directory with the specified name
+    pub fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.children(id).iter().copied().find(|c| self.nodes[c.0].name == name)
+    }
+
+    /// Add a node to the specified directory, returning the new node's id
+    fn add_node(&mut self, parent: NodeId, node_type: NodeType, name: &str, size: usize) -> NodeId {
+        assert!(self.contains(parent), "node {} has been removed", parent.0);
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(TreeNode {
+            node_type,
+            children: Vec::new(),
+            parent: Some(parent),
+            name: name.to_string(),
+            size,
+            removed: false,
+        });
+        self.nodes[parent.0].children.push(id);
+        self.grow(Some(parent), size);
+        return id;
+    }
+
+    /// Add to the size of a directory and all of the directories above it
+    fn grow(&mut self, dir: Option<NodeId>, size: usize) {
+        let mut current = dir;
+        while let Some(id) = current {
+            self.nodes[id.0].size += size;
+            current = self.nodes[id.0].parent;
+        }
+    }
+
+    /// Subtract from the size of a directory and all of the directories above it
+    fn shrink(&mut self, dir: Option<NodeId>, size: usize) {
+        let mut current = dir;
+        while let Some(id) = current {
+            self.nodes[id.0].size -= size;
+            current = self.nodes[id.0].parent;
+        }
+    }
+
+    /// Whether a node is the specified directory or somewhere beneath it
+    pub fn is_within(&self, id: NodeId, dir: NodeId) -> bool {
+        let mut current = Some(id);
+        while let Some(c) = current {
+            if c == dir {
+                return true;
+            }
+            current = self.parent(c);
+        }
+
+        return false;
+    }
+
+    /// Change the size of a file
+    pub fn set_file_size(&mut self, id: NodeId, size: usize) -> Result<(), String> {
+        let node = self.check(id)?;
+        if node.node_type != NodeType::File {
+            return Err(format!("{} is a directory", node.name));
+        }
 
-    let rm = node.borrow_mut();
-    for c in &rm.children {
-        dfs(c.clone(), callback);
+        let (old_size, parent) = (node.size, node.parent);
+        self.nodes[id.0].size = size;
+        self.shrink(parent, old_size);
+        self.grow(parent, size);
+        return Ok(());
+    }
+
+    /// Remove a node from the filesystem.  Directories are only removed if
+    /// `recursive` is set, in which case everything inside them goes too.  The ids
+    /// of removed nodes are never reused, and using them is an error (or a panic,
+    /// for methods that can't fail).
+    pub fn remove(&mut self, id: NodeId, recursive: bool) -> Result<(), String> {
+        let node = self.check(id)?;
+        let parent = node.parent.ok_or_else(|| "can't remove the root directory".to_string())?;
+        if node.node_type == NodeType::Directory && !recursive {
+            return Err(format!("{} is a directory", node.name));
+        }
+
+        let size = node.size;
+        self.nodes[parent.0].children.retain(|c| *c != id);
+        self.shrink(Some(parent), size);
+        for removed in self.descendants(id).collect::<Vec<NodeId>>() {
+            self.nodes[removed.0].removed = true;
+        }
+        return Ok(());
+    }
+
+    /// Move (and possibly rename) a node into another directory
+    pub fn move_node(&mut self, id: NodeId, dir: NodeId, name: &str) -> Result<(), String> {
+        let old_parent = self.check(id)?.parent.ok_or_else(|| "can't move the root directory".to_string())?;
+        if self.check(dir)?.node_type != NodeType::Directory {
+            return Err(format!("{} is not a directory", self.nodes[dir.0].name));
+        }
+        if self.is_within(dir, id) {
+            return Err(format!("can't move {} inside itself", self.nodes[id.0].name));
+        }
+        if let Some(existing) = self.child(dir, name) {
+            if existing == id {
+                return Ok(());
+            }
+            return Err(format!("{name} already exists"));
+        }
+
+        let size = self.nodes[id.0].size;
+        self.nodes[old_parent.0].children.retain(|c| *c != id);
+        self.shrink(Some(old_parent), size);
+
+        self.nodes[id.0].parent = Some(dir);
+        self.nodes[id.0].name = name.to_string();
+        self.nodes[dir.0].children.push(id);
+        self.grow(Some(dir), size);
+        return Ok(());
+    }
+
+    /// Add a directory to the specified directory
+    pub fn add_dir(&mut self, parent: NodeId, name: &str) -> NodeId {
+        self.add_node(parent, NodeType::Directory, name, 0)
+    }
+
+    /// Add a file to the specified directory
+    pub fn add_file(&mut self, parent: NodeId, name: &str, size: usize) -> NodeId {
+        self.add_node(parent, NodeType::File, name, size)
+    }
+
+    /// Find a node by its absolute path, e.g. "/a/e".  Empty and "." components are
+    /// ignored and ".." refers to the parent directory.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        if !path.starts_with('/') {
+            return None;
+        }
+
+        let mut id = self.root();
+        for component in path.split('/') {
+            match component {
+                "" | "." => {},
+                ".." => id = self.parent(id).unwrap_or(id),
+                name => id = self.child(id, name)?,
+            }
+        }
+
+        return Some(id);
+    }
+
+    /// Return the absolute path of a node
+    pub fn path_of(&self, id: NodeId) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            names.push(&self.nodes[current.0].name);
+            current = parent;
+        }
+
+        names.reverse();
+        return format!("/{}", names.join("/"));
+    }
+
+    /// Iterate over every node in the filesystem, depth-first, starting at the root
+    pub fn iter(&self) -> Iter<'_> {
+        self.descendants(self.root())
+    }
+
+    /// Iterate depth-first over a node and everything beneath it
+    pub fn descendants(&self, id: NodeId) -> Iter<'_> {
+        Iter { fs: self, stack: vec![id] }
     }
 }
 
-/// Use a DFS to compute a sum of directory nodes less than or equal to the specified size
-pub fn sum_dirs_le(fs: Rc<RefCell<TreeNode>>, max_size: usize) -> usize {
-    let mut dir_sizes: Vec<usize> = Vec::new();
-    let mut callback = |node: Rc<RefCell<TreeNode>>| {
-        let rm = node.borrow_mut();
-        if rm.node_type == NodeType::Directory {
-            dir_sizes.push(rm.size);
+/// A depth-first (pre-order) iterator over the nodes of a FileSystem
+pub struct Iter<'a> {
+    fs: &'a FileSystem,
+    stack: Vec<NodeId>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let id = self.stack.pop()?;
+
+        //  Push children in reverse so they are visited in order
+        self.stack.extend(self.fs.children(id).iter().rev());
+        return Some(id);
+    }
+}
+
+/// Recompute the size of every directory in the filesystem from its contents,
+/// returning the total size (the size of the root directory).  The FileSystem
+/// keeps directory sizes up to date as it changes, so this never needs calling;
+/// it's kept for existing callers and to check the sizes it keeps.
+pub fn update_sizes(fs: &mut FileSystem) -> usize {
+    //  Children always come after their parent in a depth-first walk, so walking
+    //  it backwards sizes every directory after all of its contents
+    let order = fs.iter().collect::<Vec<NodeId>>();
+    for id in order.into_iter().rev() {
+        if fs.nodes[id.0].node_type == NodeType::Directory {
+            let sz = fs.nodes[id.0].children.iter().map(|c| fs.nodes[c.0].size).sum();
+            fs.nodes[id.0].size = sz;
         }
-    };
-    dfs(fs, &mut callback);
+    }
+
+    return fs.node(fs.root()).size;
+}
+
+/// Perform a depth-first search of the tree starting at the specified node.  The
+/// callback function will be called for each node visited, to support arbitrary
+/// operations based on the graph structure.
+pub fn dfs(fs: &FileSystem, id: NodeId, callback: &mut dyn FnMut(NodeId, &TreeNode)) {
+    for n in fs.descendants(id) {
+        callback(n, fs.node(n));
+    }
+}
+
+/// A test applied to each node by a Query
+type Predicate<'a> = Box<dyn Fn(NodeId, &TreeNode) -> bool + 'a>;
 
-    let mut total_size = 0;
-    for dir_size in dir_sizes {
-        if dir_size < max_size {
-            total_size += dir_size;
+/// A query selecting nodes from a FileSystem by path, type, size or any other
+/// predicate.  Queries are built up by chaining filters and then either collect
+/// the matching nodes or aggregate their sizes, e.g.
+/// `fs.find("/a/**").files().size(1000..).sum()`.
+pub struct Query<'a> {
+    fs: &'a FileSystem,
+    glob: Option<Glob>,
+    node_type: Option<NodeType>,
+    sizes: Vec<(Bound<usize>, Bound<usize>)>,
+    predicates: Vec<Predicate<'a>>,
+}
+
+impl FileSystem {
+    /// Start a query over every node in the filesystem
+    pub fn query(&self) -> Query<'_> {
+        Query {
+            fs: self,
+            glob: None,
+            node_type: None,
+            sizes: Vec::new(),
+            predicates: Vec::new(),
         }
     }
-    return total_size;
+
+    /// Start a query over the nodes whose absolute paths match a glob pattern (see
+    /// Glob).  Patterns that don't start with '/' can match at any depth, so "*.txt"
+    /// finds every .txt file.
+    pub fn find(&self, pattern: &str) -> Query<'_> {
+        self.query().matching(pattern)
+    }
 }
 
-/// Use a DFS to find the smallest directory node greater than or equal to the specified size
-pub fn smallest_dir_gt(fs: Rc<RefCell<TreeNode>>, min_size: usize) -> usize {
-    let mut large_enough_dir_sizes: Vec<usize> = Vec::new();
-    let mut callback = |node: Rc<RefCell<TreeNode>>| {
-        let rm = node.borrow_mut();
-        if rm.node_type == NodeType::Directory && rm.size > min_size {
-            large_enough_dir_sizes.push(rm.size);
+impl<'a> Query<'a> {
+    /// Only select nodes whose absolute paths match a glob pattern
+    pub fn matching(mut self, pattern: &str) -> Self {
+        self.glob = if pattern.starts_with('/') {
+            Some(Glob::new(pattern))
+        } else {
+            Some(Glob::new(&format!("/**/{pattern}")))
+        };
+        self
+    }
+
+    /// Only select nodes of the specified type
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.node_type = Some(node_type);
+        self
+    }
+
+    /// Only select directories
+    pub fn dirs(self) -> Self {
+        self.node_type(NodeType::Directory)
+    }
+
+    /// Only select files
+    pub fn files(self) -> Self {
+        self.node_type(NodeType::File)
+    }
+
+    /// Only select nodes with a size in the specified range, e.g. `..100000`
+    pub fn size(mut self, range: impl RangeBounds<usize>) -> Self {
+        self.sizes.push((range.start_bound().cloned(), range.end_bound().cloned()));
+        self
+    }
+
+    /// Only select nodes for which the predicate is true
+    pub fn filter(mut self, predicate: impl Fn(NodeId, &TreeNode) -> bool + 'a) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Whether a node is selected by the query
+    fn selects(&self, id: NodeId) -> bool {
+        let node = self.fs.node(id);
+        return self.node_type.map_or(true, |t| t == node.node_type)
+            && self.sizes.iter().all(|r| r.contains(&node.size))
+            && self.predicates.iter().all(|p| p(id, node))
+            && self.glob.as_ref().map_or(true, |g| g.matches(&self.fs.path_of(id)));
+    }
+
+    /// Iterate over the selected nodes, depth-first
+    pub fn iter(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.fs.iter().filter(|id| self.selects(*id))
+    }
+
+    /// Return the selected nodes, depth-first
+    pub fn ids(&self) -> Vec<NodeId> {
+        self.iter().collect()
+    }
+
+    /// Return the absolute paths of the selected nodes, depth-first
+    pub fn paths(&self) -> Vec<String> {
+        self.iter().map(|id| self.fs.path_of(id)).collect()
+    }
+
+    /// Count the selected nodes
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Sum the sizes of the selected nodes
+    pub fn sum(&self) -> usize {
+        self.iter().map(|id| self.fs.node(id).size).sum()
+    }
+
+    /// Find the smallest size of the selected nodes
+    pub fn min(&self) -> Option<usize> {
+        self.iter().map(|id| self.fs.node(id).size).min()
+    }
+
+    /// Find the largest size of the selected nodes
+    pub fn max(&self) -> Option<usize> {
+        self.iter().map(|id| self.fs.node(id).size).max()
+    }
+}
+
+/// Compute a sum of directory nodes less than the specified size
+pub fn sum_dirs_le(fs: &FileSystem, max_size: usize) -> usize {
+    return fs.query().dirs().size(..max_size).sum();
+}
+
+/// Find the smallest directory node greater than the specified size, if there is one
+pub fn smallest_dir_gt(fs: &FileSystem, min_size: usize) -> Option<usize> {
+    return fs.query().dirs().size((Bound::Excluded(min_size), Bound::Unbounded)).min();
+}
+
+/// Render the tree below the specified node as an indented listing, in the same
+/// form as the puzzle description, e.g. "- a (dir, size=94853)".
+pub fn tree_report(fs: &FileSystem, id: NodeId) -> String {
+    let mut report = String::new();
+    tree_report_node(fs, id, 0, &mut report);
+    return report;
+}
+
+fn tree_report_node(fs: &FileSystem, id: NodeId, depth: usize, report: &mut String) {
+    let node = fs.node(id);
+    let kind = if node.node_type == NodeType::Directory { "dir" } else { "file" };
+    report.push_str(&format!("{}- {} ({}, size={})\n", "  ".repeat(depth), node.name, kind, node.size));
+
+    for c in fs.children(id) {
+        tree_report_node(fs, *c, depth + 1, report);
+    }
+}
+
+/// Format a size in bytes the way `du -h` does, using powers of 1024 and a
+/// single decimal place for small values, e.g. 512, 1.5K, 46M
+pub fn human_size(size: usize) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+
+    if size < 1024 {
+        return size.to_string();
+    }
+
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if value < 10.0 {
+        return format!("{:.1}{}", value, UNITS[unit]);
+    } else {
+        return format!("{:.0}{}", value, UNITS[unit]);
+    }
+}
+
+/// Return the ids of the n largest directories, largest first.  Directories of
+/// equal size are ordered by path.
+pub fn largest_dirs(fs: &FileSystem, n: usize) -> Vec<NodeId> {
+    let mut dirs = fs.iter()
+        .filter(|id| fs.node(*id).node_type == NodeType::Directory)
+        .map(|id| (fs.path_of(id), id))
+        .collect::<Vec<(String, NodeId)>>();
+    dirs.sort_by(|(path_a, a), (path_b, b)| {
+        fs.node(*b).size.cmp(&fs.node(*a).size).then_with(|| path_a.cmp(path_b))
+    });
+
+    return dirs.into_iter().take(n).map(|(_, id)| id).collect();
+}
+
+/// Produce a `du`-style summary with one line per directory, largest first: the
+/// size (human readable if requested), a tab and the directory's path
+pub fn du_report(fs: &FileSystem, human_readable: bool) -> String {
+    let mut report = String::new();
+    for id in largest_dirs(fs, usize::MAX) {
+        let size = fs.node(id).size;
+        let size_str = if human_readable { human_size(size) } else { size.to_string() };
+        report.push_str(&format!("{}\t{}\n", size_str, fs.path_of(id)));
+    }
+
+    return report;
+}
+
+/// Trees with at most this many deletable nodes are planned exactly
+const EXACT_PLAN_LIMIT: usize = 20;
+
+/// A set of files and directories to delete to free up disk space
+#[derive(Clone, Debug, PartialEq)]
+pub struct CleanupPlan {
+    /// The nodes to delete, none of which contains another
+    pub nodes: Vec<NodeId>,
+
+    /// The absolute paths of the nodes to delete
+    pub paths: Vec<String>,
+
+    /// The number of bytes freed by deleting the nodes
+    pub freed: usize,
+}
+
+impl CleanupPlan {
+    fn new(fs: &FileSystem, nodes: Vec<NodeId>) -> Self {
+        let paths = nodes.iter().map(|id| fs.path_of(*id)).collect();
+        let freed = nodes.iter().map(|id| fs.node(*id).size).sum();
+        Self { nodes, paths, freed }
+    }
+
+    /// Whether this plan is better than another: it frees fewer bytes, or the same
+    /// number of bytes by deleting fewer nodes
+    fn better_than(&self, other: &CleanupPlan) -> bool {
+        return (self.freed, self.nodes.len()) < (other.freed, other.nodes.len());
+    }
+}
+
+/// Return the number of bytes that must be freed so that at least required_free
+/// bytes of a disk of total_space bytes are unused
+fn bytes_needed(fs: &FileSystem, total_space: usize, required_free: usize) -> usize {
+    let unused = total_space.saturating_sub(fs.node(fs.root()).size);
+    return required_free.saturating_sub(unused);
+}
+
+/// Plan which files and directories to delete so that at least required_free bytes
+/// of a disk of total_space bytes are unused, deleting as few bytes as possible.
+/// The root directory is never deleted, and no deleted node is inside another.
+/// Small trees are searched exhaustively, larger ones with a greedy heuristic.
+/// Returns None if there isn't enough to delete.
+pub fn plan_cleanup(fs: &FileSystem, total_space: usize, required_free: usize) -> Option<CleanupPlan> {
+    if fs.iter().count() - 1 <= EXACT_PLAN_LIMIT {
+        return plan_cleanup_exact(fs, total_space, required_free);
+    } else {
+        return plan_cleanup_greedy(fs, total_space, required_free);
+    }
+}
+
+/// Find the best cleanup plan by searching every set of non-overlapping nodes,
+/// abandoning branches that can no longer free enough.  The search time can still
+/// grow exponentially with the size of the tree.
+pub fn plan_cleanup_exact(fs: &FileSystem, total_space: usize, required_free: usize) -> Option<CleanupPlan> {
+    let needed = bytes_needed(fs, total_space, required_free);
+
+    //  Pre-order walk (without the root), with the index just past each node's subtree
+    let order = fs.iter().skip(1).collect::<Vec<NodeId>>();
+    let mut subtree_end = vec![0; order.len()];
+    for (i, id) in order.iter().enumerate() {
+        subtree_end[i] = i + fs.descendants(*id).count();
+    }
+
+    //  The most that could be freed by deleting nodes from order[i..], which is the
+    //  size of order[i] plus whatever follows its subtree
+    let mut available = vec![0; order.len() + 1];
+    for i in (0..order.len()).rev() {
+        available[i] = fs.node(order[i]).size + available[subtree_end[i]];
+    }
+
+    let mut search = CleanupSearch { fs, order, subtree_end, available, needed, chosen: Vec::new(), best: None };
+    search.search(0, 0);
+    return search.best;
+}
+
+/// The state of the exhaustive search for a cleanup plan
+struct CleanupSearch<'a> {
+    fs: &'a FileSystem,
+    /// The deletable nodes, in pre-order
+    order: Vec<NodeId>,
+    /// The index in `order` just past each node's subtree
+    subtree_end: Vec<usize>,
+    /// The most that could be freed by deleting nodes from order[i..]
+    available: Vec<usize>,
+    /// The number of bytes that must be freed
+    needed: usize,
+    /// The nodes chosen for deletion so far
+    chosen: Vec<NodeId>,
+    /// The best plan found so far
+    best: Option<CleanupPlan>,
+}
+
+impl CleanupSearch<'_> {
+    /// Search the plans that add nodes from order[i..] to those already chosen,
+    /// which free `freed` bytes
+    fn search(&mut self, i: usize, freed: usize) {
+        //  Deleting anything more would only free more, so stop once there's enough
+        if freed >= self.needed {
+            let plan = CleanupPlan::new(self.fs, self.chosen.clone());
+            if self.best.as_ref().map_or(true, |b| plan.better_than(b)) {
+                self.best = Some(plan);
+            }
+            return;
         }
-    };
-    dfs(fs, &mut callback);
 
-    let min = large_enough_dir_sizes.iter().min();
-    return *min.unwrap();
+        //  Give up if even deleting everything that's left wouldn't free enough
+        if freed + self.available[i] < self.needed {
+            return;
+        }
+
+        //  Either delete this node (skipping everything inside it) or leave it
+        let id = self.order[i];
+        self.chosen.push(id);
+        self.search(self.subtree_end[i], freed + self.fs.node(id).size);
+        self.chosen.pop();
+
+        self.search(i + 1, freed);
+    }
+}
+
+/// Find a good (but not necessarily the best) cleanup plan quickly.  Nodes are
+/// chosen best-fit: the smallest node that frees enough on its own if there is one,
+/// otherwise the largest node available, until enough has been freed.
+pub fn plan_cleanup_greedy(fs: &FileSystem, total_space: usize, required_free: usize) -> Option<CleanupPlan> {
+    let needed = bytes_needed(fs, total_space, required_free);
+
+    let mut candidates = fs.iter().skip(1).collect::<Vec<NodeId>>();
+    candidates.sort_by_key(|id| fs.node(*id).size);
+
+    //  Nodes that are inside, or contain, a node that has been chosen
+    let mut blocked = vec![false; fs.nodes.len()];
+    let mut chosen: Vec<NodeId> = Vec::new();
+    let mut freed = 0;
+
+    while freed < needed {
+        let remaining = needed - freed;
+        let mut available = candidates.iter().copied().filter(|id| !blocked[id.0]);
+        let next = match available.clone().find(|id| fs.node(*id).size >= remaining) {
+            Some(id) => id,
+            None => available.next_back()?,
+        };
+
+        for id in fs.descendants(next) {
+            blocked[id.0] = true;
+        }
+        let mut ancestor = fs.parent(next);
+        while let Some(a) = ancestor {
+            blocked[a.0] = true;
+            ancestor = fs.parent(a);
+        }
+
+        freed += fs.node(next).size;
+        chosen.push(next);
+    }
+
+    return Some(CleanupPlan::new(fs, chosen));
 }
 
-/// Create a new TreeNode on the heap.  This may not be rust-idiomatic
-fn new_file(file_name: &str, file_size: usize, p: Option<Rc<RefCell<TreeNode>>>) -> Rc<RefCell<TreeNode>> {
-    Rc::new(RefCell::new(TreeNode {
-        node_type: NodeType::File,
-        children: Vec::new(),
-        parent: p,
-        name: file_name.to_string(),
-        size: file_size
-    }))
+/// Change directory from `current_dir` following `path`, which may be absolute or
+/// relative and may contain "." and ".." components.  Directories that haven't
+/// been listed yet are created as they're entered.
+fn change_dir(fs: &mut FileSystem, current_dir: NodeId, path: &str) -> Result<NodeId, String> {
+    let mut dir = if path.starts_with('/') { fs.root() } else { current_dir };
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => {},
+            ".." => {
+                dir = fs.parent(dir).ok_or_else(|| "already at the root directory".to_string())?;
+            },
+            name => {
+                dir = match fs.child(dir, name) {
+                    Some(id) if fs.node(id).node_type == NodeType::Directory => id,
+                    Some(_) => return Err(format!("{name} is not a directory")),
+                    None => fs.add_dir(dir, name),
+                };
+            },
+        }
+    }
+
+    return Ok(dir);
 }
 
-/// Create a new TreeNode on the heap.  This may not be rust-idiomatic
-fn new_dir(dir_name: &str, p: Option<Rc<RefCell<TreeNode>>>) -> Rc<RefCell<TreeNode>> {
-    Rc::new(RefCell::new(TreeNode {
-        node_type: NodeType::Directory,
-        children: Vec::new(),
-        parent: p,
-        name: dir_name.to_string(),
-        size: 0
-    }))
+/// Add an entry from `ls` output to a directory.  Entries that were already listed
+/// are ignored, but an entry that contradicts an earlier listing is an error.
+fn add_entry(fs: &mut FileSystem, dir: NodeId, name: &str, node_type: NodeType, size: usize) -> Result<(), String> {
+    match fs.child(dir, name) {
+        None => {
+            match node_type {
+                NodeType::Directory => fs.add_dir(dir, name),
+                NodeType::File => fs.add_file(dir, name, size),
+            };
+            Ok(())
+        },
+        Some(id) => {
+            let existing = fs.node(id);
+            if existing.node_type != node_type {
+                Err(format!("{name} was previously listed as a {}",
+                    if existing.node_type == NodeType::Directory { "directory" } else { "file" }))
+            } else if node_type == NodeType::File && existing.size != size {
+                Err(format!("{name} was previously listed with size {}", existing.size))
+            } else {
+                Ok(())
+            }
+        },
+    }
 }
 
-/// Read the provided commands/output into a tree structure
-pub fn read_fs_tree(reader: &mut dyn BufRead) -> Rc<RefCell<TreeNode>> {
-    let root = new_dir("/", None);
+/// Find an existing node from `current_dir` following `path`, which may be absolute
+/// or relative
+fn resolve(fs: &FileSystem, current_dir: NodeId, path: &str) -> Result<NodeId, String> {
+    let mut id = if path.starts_with('/') { fs.root() } else { current_dir };
+
+    for component in path.split('/') {
+        match component {
+            "" | "." => {},
+            ".." => id = fs.parent(id).ok_or_else(|| "already at the root directory".to_string())?,
+            name => id = fs.child(id, name).ok_or_else(|| format!("{path} doesn't exist"))?,
+        }
+    }
+
+    return Ok(id);
+}
 
-    let mut current_dir = root.clone();
+/// Split a path into the directory that should contain it and its final name, e.g.
+/// "a/b/c" into the node for "a/b" and "c"
+fn resolve_parent<'a>(fs: &FileSystem, current_dir: NodeId, path: &'a str) -> Result<(NodeId, &'a str), String> {
+    let trimmed = path.trim_end_matches('/');
+    let (dir_path, name) = match trimmed.rfind('/') {
+        Some(0) => ("/", &trimmed[1..]),
+        Some(i) => (&trimmed[..i], &trimmed[i + 1..]),
+        None => (".", trimmed),
+    };
+
+    if name.is_empty() || name == "." || name == ".." {
+        return Err(format!("invalid name {path}"));
+    }
+
+    let dir = resolve(fs, current_dir, dir_path)?;
+    if fs.node(dir).node_type != NodeType::Directory {
+        return Err(format!("{dir_path} is not a directory"));
+    }
+
+    return Ok((dir, name));
+}
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
+/// Run a command that changes the filesystem: mkdir, touch, rm or mv
+fn run_command(fs: &mut FileSystem, current_dir: NodeId, args: &[&str]) -> Result<(), String> {
+    match args {
+        ["mkdir", path] => {
+            let (dir, name) = resolve_parent(fs, current_dir, path)?;
+            if fs.child(dir, name).is_some() {
+                return Err(format!("{path} already exists"));
+            }
+            fs.add_dir(dir, name);
+        },
+        ["touch", size, path] => {
+            let size = size.parse::<usize>().map_err(|_| format!("invalid file size {size}"))?;
+            let (dir, name) = resolve_parent(fs, current_dir, path)?;
+            match fs.child(dir, name) {
+                Some(id) => fs.set_file_size(id, size)?,
+                None => {
+                    fs.add_file(dir, name, size);
+                },
+            }
+        },
+        ["rm", path] | ["rm", "-r", path] if !path.starts_with('-') => {
+            let id = resolve(fs, current_dir, path)?;
+            if id != fs.root() && fs.is_within(current_dir, id) {
+                return Err("can't remove the current directory".to_string());
+            }
+            fs.remove(id, args.len() == 3)?;
+        },
+        ["mv", from, to] => {
+            let id = resolve(fs, current_dir, from)?;
+
+            //  Moving onto an existing directory moves into it, keeping the name
+            match resolve(fs, current_dir, to) {
+                Ok(dir) if fs.node(dir).node_type == NodeType::Directory => {
+                    let name = fs.node(id).name.clone();
+                    fs.move_node(id, dir, &name)?;
+                },
+                Ok(_) => return Err(format!("{to} already exists")),
+                Err(_) => {
+                    let (dir, name) = resolve_parent(fs, current_dir, to)?;
+                    fs.move_node(id, dir, name)?;
+                },
+            }
+        },
+        ["mkdir", ..] => return Err("usage: mkdir <path>".to_string()),
+        ["touch", ..] => return Err("usage: touch <size> <path>".to_string()),
+        ["rm", ..] => return Err("usage: rm [-r] <path>".to_string()),
+        ["mv", ..] => return Err("usage: mv <from> <to>".to_string()),
+        _ => return Err("unknown command".to_string()),
+    }
+
+    return Ok(());
+}
+
+/// Read the provided commands/output into a tree structure.  The transcript may
+/// return to the root with `cd /` at any point, enter directories that haven't
+/// been listed (they are created), and list a directory more than once.  As well
+/// as `cd` and `ls` it may change the filesystem with `mkdir <path>`,
+/// `touch <size> <path>`, `rm [-r] <path>` and `mv <from> <to>`.
+pub fn read_fs_tree(reader: &mut dyn BufRead) -> Result<FileSystem, ParseError> {
+    let mut fs = FileSystem::new();
+
+    let mut current_dir = fs.root();
+
+    //  Whether the previous command was ls, so output lines are expected
+    let mut listing = false;
+
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let trimmed_line = line.trim();
-        //println!("input line: {trimmed_line}");
-
-        let tok = trimmed_line.split(" ").collect::<Vec<&str>>();
-
-        if trimmed_line.starts_with("$") {
-            if tok[1] == "cd" {
-                if tok[2] == "/" {
-                    // root node - do nothing; root node is added above
-                } else if tok[2] == ".." {
-                    // go up to parent dir
-                    let current_dir_clone = current_dir.clone();
-                    current_dir = current_dir_clone.borrow().parent.as_ref().unwrap().clone();
-                    
-                    //println!("changing directory to {0}", current_dir.borrow().name);
-                } else {
-                    // go to specified dir
-                    let new_dir_name = tok[2];
-                    let mut new_dir_node = current_dir.clone();
-
-                    for node in &current_dir.borrow().children {
-                        if new_dir_name == node.borrow().name {
-                            new_dir_node = node.clone();
-                            break;
-                        }
+
+        if trimmed_line.is_empty() {
+            continue;
+        }
+
+        let tok = trimmed_line.split_whitespace().collect::<Vec<&str>>();
+
+        if tok[0] == "$" {
+            if tok.len() < 2 {
+                return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "missing command"));
+            }
+
+            match tok[1] {
+                "cd" => {
+                    if tok.len() != 3 {
+                        return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "cd requires one argument"));
                     }
 
-                    current_dir = new_dir_node;
-                    //println!("changing directory to {0}", current_dir.borrow().name);
+                    current_dir = change_dir(&mut fs, current_dir, tok[2])
+                        .map_err(|e| ParseError::at(DAY, line_num + 1, &line, tok[2], &e))?;
+                    listing = false;
+                },
+                "ls" => {
+                    if tok.len() != 2 {
+                        return Err(ParseError::at(DAY, line_num + 1, &line, tok[2], "ls takes no arguments"));
+                    }
+
+                    listing = true;
+                },
+                "mkdir" | "touch" | "rm" | "mv" => {
+                    run_command(&mut fs, current_dir, &tok[1..])
+                        .map_err(|e| ParseError::at(DAY, line_num + 1, &line, tok[1], &e))?;
+                    listing = false;
+                },
+                _ => return Err(ParseError::at(DAY, line_num + 1, &line, tok[1], "unknown command")),
+            }
+        } else if !listing {
+            return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "output without an ls command"));
+        } else if tok.len() != 2 {
+            return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "expected a size (or dir) and a name"));
+        } else if tok[0] == "dir" {
+            add_entry(&mut fs, current_dir, tok[1], NodeType::Directory, 0)
+                .map_err(|e| ParseError::at(DAY, line_num + 1, &line, tok[1], &e))?;
+        } else {
+            let file_size = tok[0].parse::<usize>()
+                .map_err(|_| ParseError::at(DAY, line_num + 1, &line, tok[0], "invalid file size"))?;
+            add_entry(&mut fs, current_dir, tok[1], NodeType::File, file_size)
+                .map_err(|e| ParseError::at(DAY, line_num + 1, &line, tok[1], &e))?;
+        }
+    }
+
+    return Ok(fs);
+}
+
+/// Export the filesystem as JSON.  Each node is an object with "name", "type"
+/// ("dir" or "file") and "size" members, and directories have a "children" array.
+pub fn to_json(fs: &FileSystem) -> String {
+    let mut json = String::new();
+    to_json_node(fs, fs.root(), 0, &mut json);
+    json.push('\n');
+    return json;
+}
+
+fn to_json_node(fs: &FileSystem, id: NodeId, depth: usize, json: &mut String) {
+    let node = fs.node(id);
+    let indent = "  ".repeat(depth);
+    let kind = if node.node_type == NodeType::Directory { "dir" } else { "file" };
+
+    json.push_str(&format!("{indent}{{\n{indent}  \"name\": {},\n{indent}  \"type\": \"{kind}\",\n{indent}  \"size\": {}",
+        json::quote(&node.name), node.size));
+
+    if node.node_type == NodeType::Directory {
+        if node.children.is_empty() {
+            json.push_str(&format!(",\n{indent}  \"children\": []"));
+        } else {
+            json.push_str(&format!(",\n{indent}  \"children\": [\n"));
+            for (i, c) in node.children.iter().enumerate() {
+                if i > 0 {
+                    json.push_str(",\n");
                 }
+                to_json_node(fs, *c, depth + 2, json);
             }
-        } else if trimmed_line.starts_with("dir") {
-            // create new dir
-            let new_node = new_dir(tok[1],
-                Some(current_dir.clone()));
-            current_dir.borrow_mut().children.push(new_node);
+            json.push_str(&format!("\n{indent}  ]"));
+        }
+    }
+
+    json.push_str(&format!("\n{indent}}}"));
+}
+
+/// Read a filesystem from JSON in the form written by to_json.  Directory sizes
+/// must match the total size of their contents, and names can't contain
+/// whitespace, so that the result can always be written out with to_transcript.
+pub fn read_json(reader: &mut dyn BufRead) -> Result<FileSystem, ParseError> {
+    let text = common::read_input(DAY, reader)?;
+    let root = json::parse(DAY, &text)?;
+
+    let mut fs = FileSystem::new();
+    let (name, node_type, size) = json_node(&text, &root)?;
+    if name != "/" || node_type != NodeType::Directory {
+        return Err(json::error_at(DAY, &text, root.pos, "the root must be a directory named '/'"));
+    }
+    let root_id = fs.root();
+    json_children(&text, &root, &mut fs, root_id)?;
+    check_json_size(&text, &root, &fs, root_id, size)?;
+
+    return Ok(fs);
+}
+
+/// Read the name, type and size of a node from JSON
+fn json_node(text: &str, json: &Json) -> Result<(String, NodeType, usize), ParseError> {
+    let member = |key: &str| {
+        json.get(key).ok_or_else(|| json::error_at(DAY, text, json.pos, &format!("missing \"{key}\"")))
+    };
+
+    let name = match &member("name")?.value {
+        JsonValue::String(s) if !s.is_empty() => s.clone(),
+        _ => return Err(json::error_at(DAY, text, member("name")?.pos, "invalid name")),
+    };
+
+    let node_type = match &member("type")?.value {
+        JsonValue::String(s) if s == "dir" => NodeType::Directory,
+        JsonValue::String(s) if s == "file" => NodeType::File,
+        _ => return Err(json::error_at(DAY, text, member("type")?.pos, "type must be \"dir\" or \"file\"")),
+    };
+
+    let size = match &member("size")?.value {
+        JsonValue::Number(n) => n.parse::<usize>().ok(),
+        _ => None,
+    }.ok_or_else(|| json::error_at(DAY, text, json.get("size").map_or(json.pos, |s| s.pos), "invalid size"))?;
+
+    return Ok((name, node_type, size));
+}
+
+/// Add the children of a directory from JSON to the filesystem
+fn json_children(text: &str, json: &Json, fs: &mut FileSystem, dir: NodeId) -> Result<(), ParseError> {
+    let children = match json.get("children").map(|c| &c.value) {
+        Some(JsonValue::Array(children)) => children,
+        Some(_) => return Err(json::error_at(DAY, text, json.get("children").unwrap().pos, "children must be an array")),
+        None => return Err(json::error_at(DAY, text, json.pos, "missing \"children\"")),
+    };
+
+    for child in children {
+        let (name, node_type, size) = json_node(text, child)?;
+        if name.is_empty() || name.contains(|c: char| c == '/' || c.is_whitespace()) || name == "." || name == ".." {
+            return Err(json::error_at(DAY, text, child.get("name").unwrap().pos, "invalid name"));
+        }
+        if fs.child(dir, &name).is_some() {
+            return Err(json::error_at(DAY, text, child.pos, &format!("duplicate name {name}")));
+        }
+
+        if node_type == NodeType::File && child.get("children").is_some() {
+            return Err(json::error_at(DAY, text, child.pos, "files can't have children"));
+        }
+
+        if node_type == NodeType::Directory {
+            let id = fs.add_dir(dir, &name);
+            json_children(text, child, fs, id)?;
+            check_json_size(text, child, fs, id, size)?;
         } else {
-            // create new file
-            let new_node = new_file(tok[1],
-                tok[0].parse::<usize>().unwrap(),
-                Some(current_dir.clone()));
-            current_dir.borrow_mut().children.push(new_node);
+            fs.add_file(dir, &name, size);
+        }
+    }
+
+    return Ok(());
+}
+
+/// Check that the size given for a directory in JSON matches its contents
+fn check_json_size(text: &str, json: &Json, fs: &FileSystem, dir: NodeId, size: usize) -> Result<(), ParseError> {
+    if fs.node(dir).size != size {
+        let pos = json.get("size").map_or(json.pos, |s| s.pos);
+        return Err(json::error_at(DAY, text, pos, &format!("directory size should be {}", fs.node(dir).size)));
+    }
+
+    return Ok(());
+}
+
+/// Write a canonical `$ cd` / `$ ls` transcript that read_fs_tree will read back
+/// into an identical tree: each directory is listed once, in depth-first order.
+/// Names can't contain whitespace, since the transcript format can't express it.
+pub fn to_transcript(fs: &FileSystem) -> String {
+    let mut transcript = String::from("$ cd /\n");
+    to_transcript_dir(fs, fs.root(), &mut transcript);
+    return transcript;
+}
+
+fn to_transcript_dir(fs: &FileSystem, dir: NodeId, transcript: &mut String) {
+    transcript.push_str("$ ls\n");
+    for c in fs.children(dir) {
+        let node = fs.node(*c);
+        match node.node_type {
+            NodeType::Directory => transcript.push_str(&format!("dir {}\n", node.name)),
+            NodeType::File => transcript.push_str(&format!("{} {}\n", node.size, node.name)),
         }
     }
 
-    return root;
+    for c in fs.children(dir) {
+        let node = fs.node(*c);
+        if node.node_type == NodeType::Directory {
+            transcript.push_str(&format!("$ cd {}\n", node.name));
+            to_transcript_dir(fs, *c, transcript);
+            transcript.push_str("$ cd ..\n");
+        }
+    }
+}
+
+/// Solution for day 7
+pub struct Day7 {
+    fs: FileSystem,
+}
+
+impl Solution for Day7 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { fs: read_fs_tree(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(sum_dirs_le(&self.fs, 100000))
+    }
+
+    fn part2(&self) -> Answer {
+        let smallest_dir_size_to_delete = bytes_needed(&self.fs, 70000000, 30000000);
+        match smallest_dir_gt(&self.fs, smallest_dir_size_to_delete) {
+            Some(size) if smallest_dir_size_to_delete > 0 => Box::new(size),
+            _ => Box::new("nothing to delete"),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::TestRng;
 
-    #[test]
-    fn read_fs_tree_test1() {
-        let input = "$ cd /
+    const EXAMPLE: &str = "$ cd /
             $ ls
             dir a
             14848514 b.txt
@@ -187,34 +1095,38 @@ mod tests {
             8033020 d.log
             5626152 d.ext
             7214296 k";
-        let mut buf = input.as_bytes();
-        let fs = read_fs_tree(&mut buf);
-        
-        assert_eq!("/", fs.borrow().name);
-        assert_eq!(0, fs.borrow().size);
-        assert_eq!(4, fs.borrow().children.len());
-
-        assert_eq!("a", fs.borrow().children[0].borrow().name);
-        assert_eq!(0, fs.borrow().children[0].borrow().size);
-
-        assert_eq!("b.txt", fs.borrow().children[1].borrow().name);
-        assert_eq!(14848514, fs.borrow().children[1].borrow().size);
-        
-        assert_eq!("c.dat", fs.borrow().children[2].borrow().name);
-        assert_eq!(8504156, fs.borrow().children[2].borrow().size);
-        
-        assert_eq!(48381165, update_sizes(fs.clone()));
-
-        assert_eq!(48381165, fs.borrow().size);
-        
+
+    #[test]
+    fn read_fs_tree_test1() {
+        let mut buf = EXAMPLE.as_bytes();
+        let mut fs = read_fs_tree(&mut buf).unwrap();
+        let root = fs.root();
+
+        assert_eq!("/", fs.node(root).name);
+        assert_eq!(48381165, fs.node(root).size);
+        assert_eq!(4, fs.children(root).len());
+
+        let children = fs.children(root).to_vec();
+        assert_eq!("a", fs.node(children[0]).name);
+        assert_eq!(94853, fs.node(children[0]).size);
+
+        assert_eq!("b.txt", fs.node(children[1]).name);
+        assert_eq!(14848514, fs.node(children[1]).size);
+
+        assert_eq!("c.dat", fs.node(children[2]).name);
+        assert_eq!(8504156, fs.node(children[2]).size);
+
+        assert_eq!(48381165, update_sizes(&mut fs));
+
+        assert_eq!(48381165, fs.node(root).size);
+
         let mut dir_sizes: Vec<usize> = Vec::new();
-        let mut callback = |node: Rc<RefCell<TreeNode>>| {
-            let rm = node.borrow_mut();
-            if rm.node_type == NodeType::Directory {
-                dir_sizes.push(rm.size);
+        let mut callback = |_id: NodeId, node: &TreeNode| {
+            if node.node_type == NodeType::Directory {
+                dir_sizes.push(node.size);
             }
         };
-        dfs(fs.clone(), &mut callback);
+        dfs(&fs, root, &mut callback);
         assert_eq!(4, dir_sizes.len());
 
         let mut dirs_le_1e5 = 0;
@@ -225,10 +1137,10 @@ mod tests {
         }
         assert_eq!(2, dirs_le_1e5);
 
-        let sum = sum_dirs_le(fs.clone(), 100000);
+        let sum = sum_dirs_le(&fs, 100000);
         assert_eq!(95437, sum);
 
-        let total_used_space = fs.borrow().size;
+        let total_used_space = fs.node(root).size;
         assert_eq!(48381165, total_used_space);
 
         let total_available_space = 70000000;
@@ -239,8 +1151,598 @@ mod tests {
         let smallest_dir_size_to_delete = min_space_required - total_unused_space;
         assert_eq!(8381165, smallest_dir_size_to_delete);
 
-        let smallest_dir_size_greater_than_the_minimum_required = smallest_dir_gt(fs.clone(), 8381165);
-        assert_eq!(24933642, smallest_dir_size_greater_than_the_minimum_required);
+        let smallest_dir_size_greater_than_the_minimum_required = smallest_dir_gt(&fs, 8381165);
+        assert_eq!(Some(24933642), smallest_dir_size_greater_than_the_minimum_required);
+        assert_eq!(None, smallest_dir_gt(&fs, 48381165));
 
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn lookup_and_path_of() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!("e", fs.node(e).name);
+        assert_eq!(584, fs.node(e).size);
+        assert_eq!("/a/e", fs.path_of(e));
+        assert_eq!(Some(e), fs.lookup("/a/e/"));
+        assert_eq!(Some(e), fs.lookup("/d/../a/./e"));
+
+        let a = fs.parent(e).unwrap();
+        assert_eq!("/a", fs.path_of(a));
+        assert_eq!(Some(fs.root()), fs.parent(a));
+        assert_eq!(None, fs.parent(fs.root()));
+        assert_eq!("/", fs.path_of(fs.root()));
+        assert_eq!(Some(fs.root()), fs.lookup("/"));
+
+        assert_eq!("/d/d.log", fs.path_of(fs.lookup("/d/d.log").unwrap()));
+        assert_eq!(None, fs.lookup("/a/x"));
+        assert_eq!(None, fs.lookup("a/e"));
+    }
+
+    #[test]
+    fn iterate() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let paths = fs.iter().map(|id| fs.path_of(id)).collect::<Vec<String>>();
+        assert_eq!(vec!["/", "/a", "/a/e", "/a/e/i", "/a/f", "/a/g", "/a/h.lst", "/b.txt", "/c.dat",
+                        "/d", "/d/j", "/d/d.log", "/d/d.ext", "/d/k"], paths);
+
+        let d = fs.lookup("/d").unwrap();
+        assert_eq!(5, fs.descendants(d).count());
+        assert_eq!(vec!["j", "d.log", "d.ext", "k"],
+                   fs.children(d).iter().map(|c| fs.node(*c).name.as_str()).collect::<Vec<&str>>());
+    }
+
+    #[test]
+    fn read_fs_tree_invalid() {
+        let input = "$ cd /
+            $ ls
+            dir a
+            14848514 b.txt
+            8504x56 c.dat";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(7, err.day);
+        assert_eq!(5, err.line);
+        assert_eq!(13, err.column);
+        assert_eq!("8504x56", err.text);
+
+        let input = "$ cd /
+            $ cd ..";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(2, err.line);
+        assert_eq!("..", err.text);
+    }
+
+    #[test]
+    fn cd_root_mid_session() {
+        let input = "$ cd /
+            $ ls
+            dir a
+            $ cd a
+            $ ls
+            dir b
+            $ cd b
+            $ cd /
+            $ ls
+            dir a
+            100 c";
+        let mut buf = input.as_bytes();
+        let mut fs = read_fs_tree(&mut buf).unwrap();
+        assert_eq!(100, update_sizes(&mut fs));
+
+        let c = fs.lookup("/c").unwrap();
+        assert_eq!(Some(fs.root()), fs.parent(c));
+        assert_eq!(2, fs.children(fs.root()).len());
+        assert!(fs.lookup("/a/b/c").is_none());
+    }
+
+    #[test]
+    fn cd_unlisted_dir() {
+        let input = "$ cd /
+            $ cd x
+            $ ls
+            10 f
+            $ cd /y/z
+            $ ls
+            20 g
+            $ cd ../..
+            $ ls
+            dir x";
+        let mut buf = input.as_bytes();
+        let mut fs = read_fs_tree(&mut buf).unwrap();
+        assert_eq!(30, update_sizes(&mut fs));
+
+        assert_eq!(10, fs.node(fs.lookup("/x/f").unwrap()).size);
+        assert_eq!(20, fs.node(fs.lookup("/y/z/g").unwrap()).size);
+        assert_eq!(2, fs.children(fs.root()).len());
+    }
+
+    #[test]
+    fn repeated_ls() {
+        let input = "$ cd /
+            $ ls
+            dir a
+            100 b
+            $ ls
+            100 b
+            dir a
+            200 c";
+        let mut buf = input.as_bytes();
+        let mut fs = read_fs_tree(&mut buf).unwrap();
+        assert_eq!(300, update_sizes(&mut fs));
+        assert_eq!(3, fs.children(fs.root()).len());
+    }
+
+    #[test]
+    fn read_fs_tree_errors() {
+        let input = "$ cd /
+            $ chmod 755 a";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(2, err.line);
+        assert_eq!(15, err.column);
+        assert_eq!("chmod", err.text);
+        assert_eq!("unknown command", err.message);
+
+        let input = "$ ls
+            100 a
+            $ ls
+            200 a";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(4, err.line);
+        assert_eq!("a", err.text);
+
+        let input = "$ ls
+            100 a
+            $ cd a";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(3, err.line);
+        assert_eq!("a is not a directory", err.message);
+
+        let input = "$ cd /
+            100 a";
+        let mut buf = input.as_bytes();
+        let err = read_fs_tree(&mut buf).err().unwrap();
+        assert_eq!(2, err.line);
+        assert_eq!("output without an ls command", err.message);
+    }
+
+    #[test]
+    fn tree_listing() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let expected = "\
+- / (dir, size=48381165)
+  - a (dir, size=94853)
+    - e (dir, size=584)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir, size=24933642)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+";
+        assert_eq!(expected, tree_report(&fs, fs.root()));
+
+        let a = fs.lookup("/a").unwrap();
+        assert_eq!("- a (dir, size=94853)\n  - e (dir, size=584)\n    - i (file, size=584)\n  - f (file, size=29116)\n  - g (file, size=2557)\n  - h.lst (file, size=62596)\n",
+            tree_report(&fs, a));
+    }
+
+    #[test]
+    fn human_sizes() {
+        assert_eq!("0", human_size(0));
+        assert_eq!("1023", human_size(1023));
+        assert_eq!("1.0K", human_size(1024));
+        assert_eq!("1.5K", human_size(1536));
+        assert_eq!("93K", human_size(94853));
+        assert_eq!("24M", human_size(24933642));
+        assert_eq!("46M", human_size(48381165));
+        assert_eq!("2.0G", human_size(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn du_and_largest() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let largest = largest_dirs(&fs, 2).iter().map(|id| fs.path_of(*id)).collect::<Vec<String>>();
+        assert_eq!(vec!["/", "/d"], largest);
+        assert_eq!(4, largest_dirs(&fs, 10).len());
+
+        assert_eq!("48381165\t/\n24933642\t/d\n94853\t/a\n584\t/a/e\n", du_report(&fs, false));
+        assert_eq!("46M\t/\n24M\t/d\n93K\t/a\n584\t/a/e\n", du_report(&fs, true));
+    }
+
+    /// Check that a plan frees enough space, that its nodes don't overlap and that
+    /// its reported paths and size are consistent
+    fn check_plan(fs: &FileSystem, plan: &CleanupPlan, needed: usize) {
+        assert!(plan.freed >= needed);
+        assert_eq!(plan.freed, plan.nodes.iter().map(|id| fs.node(*id).size).sum::<usize>());
+        for (id, path) in plan.nodes.iter().zip(plan.paths.iter()) {
+            assert_eq!(Some(*id), fs.lookup(path));
+            assert_ne!(fs.root(), *id);
+            for other in plan.nodes.iter().filter(|o| *o != id) {
+                assert!(!fs.descendants(*id).any(|d| d == *other));
+            }
+        }
+    }
+
+    #[test]
+    fn cleanup_example() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        //  Deleting the file c.dat frees less than the smallest suitable directory
+        let plan = plan_cleanup(&fs, 70000000, 30000000).unwrap();
+        assert_eq!(vec!["/c.dat"], plan.paths);
+        assert_eq!(8504156, plan.freed);
+        assert_eq!(plan, plan_cleanup_greedy(&fs, 70000000, 30000000).unwrap());
+
+        //  Nothing to do if there's already enough space
+        let plan = plan_cleanup(&fs, 70000000, 1000).unwrap();
+        assert!(plan.nodes.is_empty());
+        assert_eq!(0, plan.freed);
+
+        //  Not possible even after deleting everything
+        assert!(plan_cleanup(&fs, 48381165, 48381166).is_none());
+        assert!(plan_cleanup_greedy(&fs, 48381165, 48381166).is_none());
+
+        let plan = plan_cleanup(&fs, 48381165, 48381165).unwrap();
+        assert_eq!(48381165, plan.freed);
+        assert_eq!(vec!["/a", "/b.txt", "/c.dat", "/d"], plan.paths);
+    }
+
+    #[test]
+    fn cleanup_combination() {
+        let input = "$ cd /
+            $ ls
+            dir a
+            dir b
+            $ cd a
+            $ ls
+            60 x
+            50 y
+            $ cd /b
+            $ ls
+            45 z
+            40 w";
+        let mut buf = input.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        //  95 bytes are needed, which /a/y and /b/z free exactly
+        let plan = plan_cleanup(&fs, 200, 100).unwrap();
+        check_plan(&fs, &plan, 95);
+        assert_eq!(vec!["/a/y", "/b/z"], plan.paths);
+        assert_eq!(95, plan.freed);
+
+        //  The greedy plan is valid, but deletes more
+        let plan = plan_cleanup_greedy(&fs, 200, 100).unwrap();
+        check_plan(&fs, &plan, 95);
+        assert_eq!(vec!["/a"], plan.paths);
+        assert_eq!(110, plan.freed);
+    }
+
+    #[test]
+    fn cleanup_exact_prunes() {
+        //  Without pruning this would try all 2^40 subsets of the files
+        let mut fs = FileSystem::new();
+        for i in 0..40 {
+            fs.add_file(fs.root(), &format!("f{i}"), 10);
+        }
+        let used = fs.node(fs.root()).size;
+        assert_eq!(400, used);
+
+        assert!(plan_cleanup_exact(&fs, 400, 401).is_none());
+        let plan = plan_cleanup_exact(&fs, 400, 395).unwrap();
+        assert_eq!((400, 40), (plan.freed, plan.nodes.len()));
+        let plan = plan_cleanup_exact(&fs, 400, 25).unwrap();
+        assert_eq!((30, 3), (plan.freed, plan.nodes.len()));
+    }
+
+    #[test]
+    fn cleanup_random() {
+        //  Compare the exact and greedy planners on small random trees
+        let mut rng = TestRng::new(7);
+        let mut rand = |n: u64| rng.below(n);
+
+        for _ in 0..50 {
+            let mut fs = FileSystem::new();
+            let mut dirs = vec![fs.root()];
+            for i in 0..15 {
+                let parent = dirs[rand(dirs.len() as u64) as usize];
+                if rand(3) == 0 {
+                    dirs.push(fs.add_dir(parent, &format!("d{i}")));
+                } else {
+                    fs.add_file(parent, &format!("f{i}"), 1 + rand(1000) as usize);
+                }
+            }
+            let used = fs.node(fs.root()).size;
+            let required = 1000 + rand(used as u64 + 1) as usize;
+            let needed = bytes_needed(&fs, used + 1000, required);
+
+            let exact = plan_cleanup_exact(&fs, used + 1000, required).unwrap();
+            let greedy = plan_cleanup_greedy(&fs, used + 1000, required).unwrap();
+            check_plan(&fs, &exact, needed);
+            check_plan(&fs, &greedy, needed);
+            assert!(exact.freed <= greedy.freed);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let json = to_json(&fs);
+        assert!(json.starts_with("{\n  \"name\": \"/\",\n  \"type\": \"dir\",\n  \"size\": 48381165,\n  \"children\": [\n    {\n      \"name\": \"a\",\n"));
+
+        let mut buf = json.as_bytes();
+        let reloaded = read_json(&mut buf).unwrap();
+        assert_eq!(fs, reloaded);
+        assert_eq!(json, to_json(&reloaded));
+
+        //  Empty directories and names that need escaping
+        let mut fs = FileSystem::new();
+        let d = fs.add_dir(fs.root(), "empty\"dir\"\\");
+        fs.add_dir(d, "inner");
+        let json = to_json(&fs);
+        assert_eq!(fs, read_json(&mut json.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn read_json_errors() {
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 0, \"children\": [\n  {\"name\": \"a\", \"type\": \"link\", \"size\": 1}\n]}";
+        let err = read_json(&mut input.as_bytes()).err().unwrap();
+        assert_eq!(7, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(25, err.column);
+        assert_eq!("type must be \"dir\" or \"file\"", err.message);
+
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 0, \"children\": [\n  {\"name\": \"a\", \"type\": \"file\", \"size\": -1}\n]}";
+        assert_eq!("invalid size", read_json(&mut input.as_bytes()).err().unwrap().message);
+
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 0}";
+        assert_eq!("missing \"children\"", read_json(&mut input.as_bytes()).err().unwrap().message);
+
+        let input = "{\"name\": \"x\", \"type\": \"dir\", \"size\": 0, \"children\": []}";
+        assert_eq!("the root must be a directory named '/'", read_json(&mut input.as_bytes()).err().unwrap().message);
+
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 0, \"children\": [\n  {\"name\": \"a\", \"type\": \"file\", \"size\": 1},\n  {\"name\": \"a\", \"type\": \"file\", \"size\": 1}\n]}";
+        let err = read_json(&mut input.as_bytes()).err().unwrap();
+        assert_eq!(3, err.line);
+        assert_eq!("duplicate name a", err.message);
+
+        let input = "{\"name\": \"/\", \"type\": ";
+        assert_eq!("unexpected end of input", read_json(&mut input.as_bytes()).err().unwrap().message);
+
+        //  Names the transcript format can't express
+        for name in ["a b", "a\\nb", "\\tab", "", "a/b", ".."] {
+            let input = format!("{{\"name\": \"/\", \"type\": \"dir\", \"size\": 1, \"children\": [\n  {{\"name\": \"{name}\", \"type\": \"file\", \"size\": 1}}\n]}}");
+            let err = read_json(&mut input.as_bytes()).err().unwrap();
+            assert_eq!((2, 12, "invalid name"), (err.line, err.column, err.message.as_str()), "{name}");
+        }
+    }
+
+    #[test]
+    fn json_transcript_round_trip() {
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 6, \"children\": [
+            {\"name\": \"caf\\u00e9\", \"type\": \"dir\", \"size\": 5, \"children\": [
+                {\"name\": \"\\\"q\\\"\", \"type\": \"file\", \"size\": 5}
+            ]},
+            {\"name\": \"\\ud83d\\ude00\", \"type\": \"file\", \"size\": 1}
+        ]}";
+        let fs = read_json(&mut input.as_bytes()).unwrap();
+        let transcript = to_transcript(&fs);
+        assert_eq!("$ cd /\n$ ls\ndir caf\u{e9}\n1 \u{1f600}\n$ cd caf\u{e9}\n$ ls\n5 \"q\"\n$ cd ..\n", transcript);
+
+        let reloaded = read_fs_tree(&mut transcript.as_bytes()).unwrap();
+        assert_eq!(fs, reloaded);
+    }
+
+    #[test]
+    fn transcript_round_trip() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        let transcript = to_transcript(&fs);
+        assert_eq!("\
+$ cd /
+$ ls
+dir a
+14848514 b.txt
+8504156 c.dat
+dir d
+$ cd a
+$ ls
+dir e
+29116 f
+2557 g
+62596 h.lst
+$ cd e
+$ ls
+584 i
+$ cd ..
+$ cd ..
+$ cd d
+$ ls
+4060174 j
+8033020 d.log
+5626152 d.ext
+7214296 k
+$ cd ..
+", transcript);
+
+        let mut buf = transcript.as_bytes();
+        let mut reloaded = read_fs_tree(&mut buf).unwrap();
+        assert_eq!(fs, reloaded);
+        assert_eq!(transcript, to_transcript(&reloaded));
+
+        //  A different tree isn't equal
+        let d = reloaded.lookup("/d").unwrap();
+        reloaded.add_file(d, "extra", 1);
+        assert_ne!(fs, reloaded);
+    }
+
+    /// Check that every directory's size matches a full recomputation
+    fn check_sizes(fs: &mut FileSystem) {
+        let sizes = fs.iter().map(|id| fs.node(id).size).collect::<Vec<usize>>();
+        update_sizes(fs);
+        assert_eq!(sizes, fs.iter().map(|id| fs.node(id).size).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn mutating_commands() {
+        let input = EXAMPLE.to_string() + "
+            $ cd /
+            $ mkdir tmp
+            $ mkdir tmp/x
+            $ touch 1000 tmp/x/big
+            $ touch 5 /tmp/small
+            $ touch 2000 tmp/x/big
+            $ rm b.txt
+            $ mv d/k tmp
+            $ mv a/e/i tmp/x/renamed
+            $ mv tmp/x /a
+            $ rm -r d";
+        let mut buf = input.as_bytes();
+        let mut fs = read_fs_tree(&mut buf).unwrap();
+        check_sizes(&mut fs);
+
+        assert_eq!(7214296 + 5, fs.node(fs.lookup("/tmp").unwrap()).size);
+        assert_eq!(2000 + 584, fs.node(fs.lookup("/a/x").unwrap()).size);
+        assert_eq!(0, fs.node(fs.lookup("/a/e").unwrap()).size);
+        assert_eq!(94853 + 2000, fs.node(fs.lookup("/a").unwrap()).size);
+        assert_eq!(94853 + 2000 + 8504156 + 7214296 + 5, fs.node(fs.root()).size);
+        assert!(fs.lookup("/b.txt").is_none());
+        assert!(fs.lookup("/d").is_none());
+        assert_eq!(vec!["/a", "/c.dat", "/tmp"],
+            fs.children(fs.root()).iter().map(|id| fs.path_of(*id)).collect::<Vec<String>>());
+    }
+
+    #[test]
+    fn mutating_command_errors() {
+        let errors = [
+            ("$ mkdir a", "a already exists"),
+            ("$ mkdir x/y", "x doesn't exist"),
+            ("$ mkdir b.txt/y", "b.txt is not a directory"),
+            ("$ touch big c.dat", "invalid file size big"),
+            ("$ touch 1 a", "a is a directory"),
+            ("$ rm a", "a is a directory"),
+            ("$ rm /", "can't remove the root directory"),
+            ("$ rm x", "x doesn't exist"),
+            ("$ cd a/e\n$ rm -r /a", "can't remove the current directory"),
+            ("$ mv a a/e", "can't move a inside itself"),
+            ("$ mv b.txt c.dat", "c.dat already exists"),
+            ("$ mv / a", "can't move the root directory"),
+            ("$ rm -r", "usage: rm [-r] <path>"),
+            ("$ mv a", "usage: mv <from> <to>"),
+        ];
+
+        for (command, message) in errors {
+            let input = EXAMPLE.to_string() + "\n$ cd /\n" + command;
+            let mut buf = input.as_bytes();
+            let err = read_fs_tree(&mut buf).err().unwrap();
+            assert_eq!(message, err.message, "{command}");
+        }
+    }
+
+    #[test]
+    fn filesystem_changes() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_dir(fs.root(), "a");
+        let b = fs.add_dir(a, "b");
+        let f = fs.add_file(b, "f", 10);
+        assert_eq!(10, fs.node(a).size);
+
+        fs.set_file_size(f, 25).unwrap();
+        assert_eq!(25, fs.node(fs.root()).size);
+
+        fs.move_node(b, fs.root(), "c").unwrap();
+        assert_eq!(0, fs.node(a).size);
+        assert_eq!(Some(f), fs.lookup("/c/f"));
+        assert!(fs.is_within(f, fs.root()));
+        assert!(!fs.is_within(f, a));
+
+        fs.remove(f, false).unwrap();
+        assert_eq!(0, fs.node(fs.root()).size);
+        assert!(fs.lookup("/c/f").is_none());
+        check_sizes(&mut fs);
+
+        //  Removed nodes can't be used, including those inside a removed directory
+        let g = fs.add_file(b, "g", 5);
+        fs.remove(b, true).unwrap();
+        assert!(!fs.contains(b) && !fs.contains(g) && !fs.contains(f));
+        assert!(fs.get(g).is_none());
+        assert_eq!(Err("node 3 has been removed".to_string()), fs.set_file_size(f, 1));
+        assert!(fs.remove(g, false).is_err());
+        assert!(fs.move_node(g, fs.root(), "g").is_err());
+        assert!(fs.move_node(a, b, "a").is_err());
+        assert_eq!((true, 0), (fs.contains(a), fs.node(fs.root()).size));
+        check_sizes(&mut fs);
+    }
+
+    #[test]
+    #[should_panic(expected = "node 2 has been removed")]
+    fn removed_node_path() {
+        let mut fs = FileSystem::new();
+        let a = fs.add_dir(fs.root(), "a");
+        let b = fs.add_dir(a, "b");
+        fs.remove(a, true).unwrap();
+        fs.path_of(b);
+    }
+
+    #[test]
+    fn json_directory_size_mismatch() {
+        let input = "{\"name\": \"/\", \"type\": \"dir\", \"size\": 3, \"children\": [\n  {\"name\": \"a\", \"type\": \"file\", \"size\": 2}\n]}";
+        let err = read_json(&mut input.as_bytes()).err().unwrap();
+        assert_eq!("directory size should be 2", err.message);
+        assert_eq!(38, err.column);
+    }
+
+    #[test]
+    fn queries() {
+        let mut buf = EXAMPLE.as_bytes();
+        let fs = read_fs_tree(&mut buf).unwrap();
+
+        assert_eq!(14, fs.query().count());
+        assert_eq!(4, fs.query().dirs().count());
+        assert_eq!(10, fs.query().files().count());
+        assert_eq!(48381165, fs.query().files().sum());
+
+        assert_eq!(vec!["/b.txt"], fs.find("*.txt").paths());
+        assert_eq!(vec!["/a/e/i", "/a/f", "/a/g", "/a/h.lst"], fs.find("/a/**").files().paths());
+        assert_eq!(vec!["/a/e", "/a/f", "/a/g"], fs.find("/a/?").paths());
+        assert_eq!(vec!["/d/d.log", "/d/d.ext"], fs.find("d.*").paths());
+        assert_eq!(vec!["/a/e", "/a/e/i"], fs.find("e**").paths());
+        assert_eq!(2, fs.find("/[ab]*").count());
+        assert_eq!(0, fs.find("/x/**").count());
+
+        let files = fs.query().files();
+        assert_eq!(Some(584), files.min());
+        assert_eq!(Some(14848514), files.max());
+        assert_eq!(None, fs.find("/x").min());
+
+        assert_eq!(4, fs.query().files().size(..10000000).size(1000..).filter(|_, n| n.name.len() == 1).count());
+        assert_eq!(4, fs.query().files().size(..10000000).size(5000000..).count());
+        assert_eq!(vec!["/a", "/a/e"], fs.query().dirs().size(..=100000).paths());
+        assert_eq!(95437, fs.query().dirs().size(..100000).sum());
+        assert_eq!(Some(24933642), fs.query().dirs().size(8381166..).min());
+
+        let ids = fs.find("/a/e/*").ids();
+        assert_eq!(vec![fs.lookup("/a/e/i").unwrap()], ids);
+    }
+}
@@ -1,20 +1,27 @@
 use std::io::BufRead;
 
-use crate::common::Grid;
+use crate::common::{self, Grid, ParseError};
+use crate::solution::{Answer, Solution};
 
-impl Grid {
+const DAY: u32 = 8;
+
+/// Per-tree checks that rescan the tree's row and column, O(rows + cols) per tree.
+/// They're simple enough to trust, so the tests check `visibility_grid` and
+/// `scenic_score_grid` against them.
+#[cfg(test)]
+impl Grid<u32> {
     /// Check whether the tree at the specified grid location is visible
-    pub fn visible(&self, row: usize, col: usize) -> bool {
+    fn visible(&self, row: usize, col: usize) -> bool {
         // If this tree is at the edge of the grid, it is visible
-        if row == 0 || row == self.rows || col == 0 || col == self.cols {
+        if row == 0 || row == self.rows - 1 || col == 0 || col == self.cols - 1 {
             return true;
         }
 
-        let this_tree_height = self.get(row, col);
+        let this_tree_height = self[(row, col)];
 
         let mut visible_from_left = true;
         for i in 0..col {
-            if self.get(row, i) >= this_tree_height {
+            if self[(row, i)] >= this_tree_height {
                 //  This tree is not visible from the left
                 visible_from_left = false;
             }
@@ -22,7 +29,7 @@ impl Grid {
 
         let mut visible_from_right = true;
         for i in col+1..self.cols {
-            if self.get(row, i) >= this_tree_height {
+            if self[(row, i)] >= this_tree_height {
                 // This tree is not visible from the right
                 visible_from_right = false;
             }
@@ -30,7 +37,7 @@ impl Grid {
 
         let mut visible_from_top = true;
         for i in 0..row {
-            if self.get(i, col) >= this_tree_height {
+            if self[(i, col)] >= this_tree_height {
                 // This tree is not visible from the top
                 visible_from_top = false;
             }
@@ -38,7 +45,7 @@ impl Grid {
 
         let mut visible_from_bottom = true;
         for i in row+1..self.rows {
-            if self.get(i, col) >= this_tree_height {
+            if self[(i, col)] >= this_tree_height {
                 // This tree is not visible from the bottom
                 visible_from_bottom = false;
             }
@@ -48,13 +55,13 @@ impl Grid {
     }
 
     /// Calculate the scenic score for the specified grid location
-    pub fn scenic_score(&self, row: usize, col: usize) -> u32 {
-        let this_tree_height = self.get(row, col);
+    fn scenic_score(&self, row: usize, col: usize) -> u64 {
+        let this_tree_height = self[(row, col)];
 
-        let mut dist_left = 0;
+        let mut dist_left: u64 = 0;
         for i in (0..col).rev() {
             dist_left += 1;
-            if self.get(row, i) >= this_tree_height {
+            if self[(row, i)] >= this_tree_height {
                 break;
             }
         }
@@ -62,7 +69,7 @@ impl Grid {
         let mut dist_right = 0;
         for i in col+1..self.cols {
             dist_right += 1;
-            if self.get(row, i) >= this_tree_height {
+            if self[(row, i)] >= this_tree_height {
                 break;
             }
         }
@@ -70,7 +77,7 @@ impl Grid {
         let mut dist_up = 0;
         for i in (0..row).rev() {
             dist_up += 1;
-            if self.get(i, col) >= this_tree_height {
+            if self[(i, col)] >= this_tree_height {
                 break;
             }
         }
@@ -78,7 +85,7 @@ impl Grid {
         let mut dist_down = 0;
         for i in row+1..self.rows {
             dist_down += 1;
-            if self.get(i, col) >= this_tree_height {
+            if self[(i, col)] >= this_tree_height {
                 break;
             }
         }
@@ -87,66 +94,246 @@ impl Grid {
     }
 }
 
-/// Read tree heights from the input into a Grid
-pub fn read_tree_heights(reader: &mut dyn BufRead) -> Grid {
-    let mut grid: Grid = Grid::new(10, 10);
-    let mut row: usize = 0;
+/// Read tree heights from the input into a Grid.  The forest may be any
+/// rectangular shape, but every row must be the same width.
+pub fn read_tree_heights(reader: &mut dyn BufRead) -> Result<Grid<u32>, ParseError> {
+    let input = common::read_input(DAY, reader)?;
+
+    return Grid::parse_with(&input, |c| c.to_digit(10))
+        .map_err(|e| ParseError { day: DAY, ..e });
+}
+
+/// Call `f` with the locations along every line of sight into the grid: each row
+/// looking in from the left and from the right, and each column looking in from
+/// the top and from the bottom
+fn for_each_sight_line(grid: &Grid<u32>, mut f: impl FnMut(&[(usize, usize)])) {
+    let mut line: Vec<(usize, usize)> = Vec::with_capacity(grid.rows.max(grid.cols));
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
-        let trimmed_line = line.trim();
+    for row in 0..grid.rows {
+        line.clear();
+        line.extend((0..grid.cols).map(|col| (row, col)));
+        f(&line);
+        line.reverse();
+        f(&line);
+    }
 
-        if row == 0 {
-            grid.resize(trimmed_line.len(), trimmed_line.len());
+    for col in 0..grid.cols {
+        line.clear();
+        line.extend((0..grid.rows).map(|row| (row, col)));
+        f(&line);
+        line.reverse();
+        f(&line);
+    }
+}
+
+/// Determine the visibility of every tree in the grid.  Each line of sight is swept
+/// once while tracking the tallest tree seen so far, so this is O(rows * cols).
+pub fn visibility_grid(grid: &Grid<u32>) -> Grid<bool> {
+    let mut visible: Grid<bool> = Grid::new(grid.rows, grid.cols);
+
+    for_each_sight_line(grid, |line| {
+        let mut tallest: Option<u32> = None;
+        for &pos in line {
+            let height = grid[pos];
+            if tallest.map_or(true, |t| height > t) {
+                visible[pos] = true;
+                tallest = Some(height);
+            }
         }
-        
-        let mut col: usize = 0;
-        for c in trimmed_line.chars() {
-            let value = c.to_digit(10).unwrap();
-            grid.set(row, col, value);
-            col += 1;
+    });
+
+    return visible;
+}
+
+/// Calculate the scenic score of every tree in the grid.  For each line of sight a
+/// stack of trees that could still block the view is maintained (a monotonic stack),
+/// giving each tree's viewing distance back along the line in amortized O(1), so this
+/// is O(rows * cols).  Scores can exceed a u32 in large forests, so they're u64.
+pub fn scenic_score_grid(grid: &Grid<u32>) -> Grid<u64> {
+    let mut scores: Grid<u64> = Grid::filled(grid.rows, grid.cols, 1);
+    let mut stack: Vec<usize> = Vec::new();
+
+    for_each_sight_line(grid, |line| {
+        stack.clear();
+        for (i, &pos) in line.iter().enumerate() {
+            let height = grid[pos];
+
+            //  Trees shorter than this one can no longer block the view of later trees
+            while let Some(&top) = stack.last() {
+                if grid[line[top]] >= height {
+                    break;
+                }
+                stack.pop();
+            }
+
+            //  The view stops at the nearest tree at least as tall, or at the edge
+            let distance = match stack.last() {
+                Some(&top) => i - top,
+                None => i,
+            };
+            scores[pos] *= distance as u64;
+
+            stack.push(i);
         }
+    });
+
+    return scores;
+}
+
+/// Count the number of visible trees in the input Grid
+pub fn count_visible_trees(grid: &Grid<u32>) -> u32 {
+    let visible = visibility_grid(grid);
+    return visible.iter().filter(|(_, v)| **v).count() as u32;
+}
+
+/// Find the highest scenic score in the grid
+pub fn highest_scenic_score(grid: &Grid<u32>) -> u64 {
+    let scores = scenic_score_grid(grid);
+    return scores.iter().map(|(_, s)| *s).max().unwrap_or(0);
+}
+
+/// A layer of information about the forest that can be rendered as an image
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForestLayer {
+    /// Tree heights (0-9)
+    Heights,
+    /// Whether each tree is visible from outside the forest
+    Visibility,
+    /// The scenic score of each tree
+    ScenicScore,
+}
+
+/// Colour used to highlight the tree with the highest scenic score
+const HIGHLIGHT: (u8, u8, u8) = (0, 255, 255);
+
+/// Characters used to draw intensity in plain ASCII, from lowest to highest
+const ASCII_RAMP: &[u8] = b" .:-=+*#%@";
+
+/// Find the location of the tree with the highest scenic score (the first one, in
+/// row-major order, if several share the highest score).  Returns None if no tree
+/// scores more than 0, as happens in any forest only one tree wide or deep.
+pub fn best_scenic_location(grid: &Grid<u32>) -> Option<(usize, usize)> {
+    let scores = scenic_score_grid(grid);
+    let mut best: Option<((usize, usize), u64)> = None;
 
-        row += 1;
+    for (pos, score) in scores.iter() {
+        if best.map_or(true, |(_, s)| *score > s) {
+            best = Some((pos, *score));
+        }
     }
 
-    return grid;
+    return best.filter(|(_, score)| *score > 0).map(|(pos, _)| pos);
 }
 
-/// Count the number of visible trees in the input Grid
-pub fn count_visible_trees(grid: &Grid) -> u32 {
-    let mut visible_trees = 0;
+/// Compute the intensity (from 0.0 to 1.0) of every tree for the specified layer.
+/// Scenic scores cover a huge range, so they're square-rooted to keep low-scoring
+/// trees distinguishable.
+fn layer_intensity(grid: &Grid<u32>, layer: ForestLayer) -> Grid<f64> {
+    match layer {
+        ForestLayer::Heights => grid.map(|h| *h as f64 / 9.0),
+        ForestLayer::Visibility => visibility_grid(grid).map(|v| if *v { 1.0 } else { 0.0 }),
+        ForestLayer::ScenicScore => {
+            let scores = scenic_score_grid(grid);
+            let max = scores.iter().map(|(_, s)| *s).max().unwrap_or(0);
+            if max == 0 {
+                scores.map(|_| 0.0)
+            } else {
+                scores.map(|s| (*s as f64).sqrt() / (max as f64).sqrt())
+            }
+        },
+    }
+}
+
+/// Map an intensity (from 0.0 to 1.0) onto a black-red-yellow-white heat colour
+fn heat_colour(intensity: f64) -> (u8, u8, u8) {
+    let channel = |offset: f64| ((intensity * 3.0 - offset).clamp(0.0, 1.0) * 255.0).round() as u8;
+    return (channel(0.0), channel(1.0), channel(2.0));
+}
+
+/// Render a layer as a plain PGM greyscale image
+pub fn render_pgm(grid: &Grid<u32>, layer: ForestLayer) -> String {
+    let intensity = layer_intensity(grid, layer);
+    return intensity.to_pgm(255, |i| (i * 255.0).round() as u16);
+}
+
+/// Render a layer as a plain PPM heatmap, with the tree that has the highest
+/// scenic score highlighted
+pub fn render_ppm(grid: &Grid<u32>, layer: ForestLayer) -> String {
+    let intensity = layer_intensity(grid, layer);
+    let best = best_scenic_location(grid);
+
+    return intensity.to_ppm(|pos, i| {
+        if Some(pos) == best { HIGHLIGHT } else { heat_colour(*i) }
+    });
+}
+
+/// Render a layer for an ANSI (24-bit colour) terminal.  Each tree is drawn as its
+/// height on a background coloured by the layer, with the tree that has the highest
+/// scenic score highlighted.
+pub fn render_ansi(grid: &Grid<u32>, layer: ForestLayer) -> String {
+    let intensity = layer_intensity(grid, layer);
+    let best = best_scenic_location(grid);
+    let mut out = String::new();
 
     for row in 0..grid.rows {
         for col in 0..grid.cols {
-            if grid.visible(row, col) {
-                visible_trees += 1;
-            }
+            let (r, g, b) = if Some((row, col)) == best { HIGHLIGHT } else { heat_colour(intensity[(row, col)]) };
+
+            //  Pick a text colour that stands out against the background
+            let fg = if (r as u32 + g as u32 + b as u32) > 382 { 30 } else { 97 };
+            out.push_str(&format!("\x1b[48;2;{r};{g};{b}m\x1b[{fg}m{}", grid[(row, col)]));
         }
+        out.push_str("\x1b[0m\n");
     }
 
-    return visible_trees;
+    return out;
 }
 
-/// Find the highest scenic score in the grid
-pub fn highest_scenic_score(grid: &Grid) -> u32 {
-    let mut highest_score = 0;
+/// Render a layer as plain ASCII art, using a character ramp for intensity and
+/// marking the tree that has the highest scenic score with an 'X'
+pub fn render_ascii(grid: &Grid<u32>, layer: ForestLayer) -> String {
+    let intensity = layer_intensity(grid, layer);
+    let best = best_scenic_location(grid);
+    let mut out = String::new();
 
     for row in 0..grid.rows {
         for col in 0..grid.cols {
-            let score = grid.scenic_score(row, col);
-            if score > highest_score {
-                highest_score = score;
+            if Some((row, col)) == best {
+                out.push('X');
+            } else {
+                let level = (intensity[(row, col)] * (ASCII_RAMP.len() - 1) as f64).round() as usize;
+                out.push(ASCII_RAMP[level] as char);
             }
         }
+        out.push('\n');
+    }
+
+    return out;
+}
+
+/// Solution for day 8
+pub struct Day8 {
+    trees: Grid<u32>,
+}
+
+impl Solution for Day8 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { trees: read_tree_heights(reader)? })
     }
 
-    return highest_score;
+    fn part1(&self) -> Answer {
+        Box::new(count_visible_trees(&self.trees))
+    }
+
+    fn part2(&self) -> Answer {
+        Box::new(highest_scenic_score(&self.trees))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::TestRng;
 
     #[test]
     fn visible_from_left() {
@@ -154,7 +341,7 @@ mod tests {
                      011
                      111";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(trees.visible(1, 1));
     }
 
@@ -164,7 +351,7 @@ mod tests {
                      110
                      111";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(trees.visible(1, 1));
     }
 
@@ -174,7 +361,7 @@ mod tests {
                      111
                      111";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(trees.visible(1, 1));
     }
 
@@ -184,7 +371,7 @@ mod tests {
                      111
                      101";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(trees.visible(1, 1));
     }
 
@@ -194,7 +381,7 @@ mod tests {
                      111
                      111";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(!trees.visible(1, 1));
     }
 
@@ -204,7 +391,7 @@ mod tests {
                      111
                      111";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
         assert!(trees.visible(0, 0));
         assert!(trees.visible(2, 2));
     }
@@ -217,7 +404,7 @@ mod tests {
                      33549
                      35390";
         let mut buf = input.as_bytes();
-        let trees = read_tree_heights(&mut buf);
+        let trees = read_tree_heights(&mut buf).unwrap();
 
         // tests from problem description
         assert!( trees.visible(1, 1));
@@ -238,4 +425,194 @@ mod tests {
         let highest_score = highest_scenic_score(&trees);
         assert_eq!(8, highest_score);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn invalid_tree_height() {
+        let input = "303
+                     2a5
+                     653";
+        let mut buf = input.as_bytes();
+        let err = read_tree_heights(&mut buf).err().unwrap();
+        assert_eq!(8, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(23, err.column);
+        assert_eq!("a", err.text);
+    }
+
+    /// Generate a forest of pseudo-random tree heights
+    fn random_forest(rows: usize, cols: usize, seed: u64) -> Grid<u32> {
+        let mut rng = TestRng::new(seed);
+        let heights = (0..rows * cols).map(|_| rng.below(10) as u32).collect();
+        Grid::from_vec(rows, cols, heights).unwrap()
+    }
+
+    #[test]
+    fn sweep_matches_per_tree_checks() {
+        for (seed, (rows, cols)) in [(1, 1), (1, 7), (7, 1), (5, 5), (13, 31), (40, 17)].into_iter().enumerate() {
+            let trees = random_forest(rows, cols, seed as u64);
+            let visible = visibility_grid(&trees);
+            let scores = scenic_score_grid(&trees);
+
+            for row in 0..rows {
+                for col in 0..cols {
+                    assert_eq!(trees.visible(row, col), visible[(row, col)], "visible ({row}, {col}) in {rows}x{cols}");
+                    assert_eq!(trees.scenic_score(row, col), scores[(row, col)], "scenic score ({row}, {col}) in {rows}x{cols}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sweep_large_forest() {
+        let trees = random_forest(1000, 1000, 42);
+
+        //  Only spot-check the (slow) per-tree methods on a large forest
+        let visible = visibility_grid(&trees);
+        let scores = scenic_score_grid(&trees);
+        for i in (0..1000).step_by(97) {
+            assert_eq!(trees.visible(i, 999 - i), visible[(i, 999 - i)]);
+            assert_eq!(trees.scenic_score(i, 999 - i), scores[(i, 999 - i)]);
+        }
+
+        assert!(count_visible_trees(&trees) >= 3996);
+        assert!(highest_scenic_score(&trees) > 0);
+    }
+
+    #[test]
+    fn scenic_score_overflows_u32() {
+        //  A single tall tree in the middle of a flat forest sees 500 trees in every
+        //  direction, for a score of 500^4, which doesn't fit in a u32
+        let mut trees: Grid<u32> = Grid::new(1001, 1001);
+        trees[(500, 500)] = 9;
+
+        let expected: u64 = 500 * 500 * 500 * 500;
+        assert!(expected > u32::MAX as u64);
+        assert_eq!(expected, trees.scenic_score(500, 500));
+        assert_eq!(expected, scenic_score_grid(&trees)[(500, 500)]);
+        assert_eq!(expected, highest_scenic_score(&trees));
+        assert_eq!(Some((500, 500)), best_scenic_location(&trees));
+    }
+
+    #[test]
+    fn wide_forest() {
+        let input = "3037325
+                     2551262
+                     6533208";
+        let mut buf = input.as_bytes();
+        let trees = read_tree_heights(&mut buf).unwrap();
+        assert_eq!(3, trees.rows);
+        assert_eq!(7, trees.cols);
+        assert_eq!(8, trees[(2, 6)]);
+
+        assert!( trees.visible(1, 1));
+        assert!( trees.visible(1, 2));
+        assert!(!trees.visible(1, 3));
+        assert!(!trees.visible(1, 4));
+        assert!( trees.visible(1, 5));
+        assert!( trees.visible(1, 6));
+        assert_eq!(19, count_visible_trees(&trees));
+        assert_eq!(3, trees.scenic_score(1, 2));
+        assert_eq!(5, trees.scenic_score(1, 5));
+        assert_eq!(5, highest_scenic_score(&trees));
+    }
+
+    #[test]
+    fn tall_forest() {
+        let input = "303
+                     255
+                     653
+                     335
+                     353
+                     123";
+        let mut buf = input.as_bytes();
+        let trees = read_tree_heights(&mut buf).unwrap();
+        assert_eq!(6, trees.rows);
+        assert_eq!(3, trees.cols);
+
+        assert!( trees.visible(1, 1));
+        assert!( trees.visible(2, 1));
+        assert!(!trees.visible(3, 1));
+        assert!( trees.visible(4, 1));
+        assert!( trees.visible(5, 1));
+        assert_eq!(17, count_visible_trees(&trees));
+        assert_eq!(2, trees.scenic_score(4, 1));
+        assert_eq!(2, highest_scenic_score(&trees));
+    }
+
+    #[test]
+    fn ragged_forest() {
+        let input = "303
+                     2551
+                     653";
+        let mut buf = input.as_bytes();
+        let err = read_tree_heights(&mut buf).err().unwrap();
+        assert_eq!(8, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(22, err.column);
+        assert_eq!("2551", err.text);
+
+        let input = "303
+                     25
+                     653";
+        let mut buf = input.as_bytes();
+        let err = read_tree_heights(&mut buf).err().unwrap();
+        assert_eq!(2, err.line);
+        assert_eq!("25", err.text);
+    }
+
+    #[test]
+    fn best_scenic_location_test() {
+        let input = "30373
+                     25512
+                     65332
+                     33549
+                     35390";
+        let mut buf = input.as_bytes();
+        let trees = read_tree_heights(&mut buf).unwrap();
+        assert_eq!(Some((3, 2)), best_scenic_location(&trees));
+
+        let empty: Grid<u32> = Grid::new(0, 0);
+        assert_eq!(None, best_scenic_location(&empty));
+
+        //  Every tree is on the edge, so they all score 0 and none is best
+        let mut buf = "30373".as_bytes();
+        let row = read_tree_heights(&mut buf).unwrap();
+        assert_eq!(None, best_scenic_location(&row));
+        assert_eq!("- -#-\n", render_ascii(&row, ForestLayer::Heights));
+        assert!(!render_ansi(&row, ForestLayer::Heights).contains("\x1b[48;2;0;255;255m"));
+    }
+
+    #[test]
+    fn render_images() {
+        let input = "303
+                     282
+                     303";
+        let mut buf = input.as_bytes();
+        let trees = read_tree_heights(&mut buf).unwrap();
+
+        assert_eq!("P2\n3 3\n255\n85 0 85\n57 227 57\n85 0 85\n", render_pgm(&trees, ForestLayer::Heights));
+        assert_eq!("P2\n3 3\n255\n255 255 255\n255 255 255\n255 255 255\n", render_pgm(&trees, ForestLayer::Visibility));
+        assert_eq!("P2\n3 3\n255\n0 0 0\n0 255 0\n0 0 0\n", render_pgm(&trees, ForestLayer::ScenicScore));
+
+        let ppm = render_ppm(&trees, ForestLayer::ScenicScore);
+        assert_eq!("P3\n3 3\n255\n0 0 0  0 0 0  0 0 0\n0 0 0  0 255 255  0 0 0\n0 0 0  0 0 0  0 0 0\n", ppm);
+    }
+
+    #[test]
+    fn render_text() {
+        let input = "303
+                     282
+                     303";
+        let mut buf = input.as_bytes();
+        let trees = read_tree_heights(&mut buf).unwrap();
+
+        assert_eq!("- -\n:X:\n- -\n", render_ascii(&trees, ForestLayer::Heights));
+        assert_eq!("   \n X \n   \n", render_ascii(&trees, ForestLayer::ScenicScore));
+
+        let ansi = render_ansi(&trees, ForestLayer::Visibility);
+        assert_eq!(3, ansi.lines().count());
+        assert!(ansi.starts_with("\x1b[48;2;255;255;255m\x1b[30m3"));
+        assert!(ansi.contains("\x1b[48;2;0;255;255m\x1b[30m8"));
+        assert!(ansi.ends_with("\x1b[0m\n"));
+    }
+}
@@ -1,5 +1,10 @@
 use std::{io::BufRead, collections::HashSet};
 
+use crate::common::ParseError;
+use crate::solution::{Answer, Solution};
+
+const DAY: u32 = 9;
+
 /// Enum to describe movement direction
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Direction {
@@ -43,18 +48,18 @@ impl Rope {
 }
 
 /// Read input movements into a vector
-pub fn read_movements(reader: &mut dyn BufRead) -> Vec<Movement> {
+pub fn read_movements(reader: &mut dyn BufRead) -> Result<Vec<Movement>, ParseError> {
     let mut rmv: Vec<Movement> = Vec::new();
 
-    for line_result in reader.lines() {
-        let line = line_result.unwrap();
+    for (line_num, line_result) in reader.lines().enumerate() {
+        let line = line_result.map_err(|e| ParseError::io(DAY, line_num + 1, &e))?;
         let trimmed_line = line.trim();
 
         let mut rm = Movement{ direction : Direction::Up, distance : 0, };
 
-        let tokens = trimmed_line.split(" ").collect::<Vec<&str>>();
+        let tokens = trimmed_line.split_whitespace().collect::<Vec<&str>>();
         if tokens.len() != 2 {
-            panic!("Unexpected number of tokens");
+            return Err(ParseError::at(DAY, line_num + 1, &line, trimmed_line, "unexpected number of tokens"));
         }
 
         match tokens[0] {
@@ -62,15 +67,18 @@ pub fn read_movements(reader: &mut dyn BufRead) -> Vec<Movement> {
             "D" => rm.direction = Direction::Down,
             "L" => rm.direction = Direction::Left,
             "R" => rm.direction = Direction::Right,
-            _ => panic!("invalid direction"),
+            _ => return Err(ParseError::at(DAY, line_num + 1, &line, tokens[0], "invalid direction")),
         }
 
-        rm.distance = tokens[1].parse::<i32>().unwrap();
+        rm.distance = match tokens[1].parse::<i32>() {
+            Ok(d) if d >= 0 => d,
+            _ => return Err(ParseError::at(DAY, line_num + 1, &line, tokens[1], "invalid distance")),
+        };
 
         rmv.push(rm);
     }
 
-    return rmv;
+    return Ok(rmv);
 }
 
 /// Move the head of the rope one unit in the specified direction
@@ -87,7 +95,7 @@ fn move_head(pos: &mut RopeVertex, direction: Direction) {
 fn move_vertex(rope: &mut Rope, index: usize) {
 
     let lead_vertex = rope.vertices[index - 1];
-    let mut vertex = &mut rope.vertices[index];
+    let vertex = &mut rope.vertices[index];
 
     let dx = (lead_vertex.x - vertex.x).abs();
     let dy = (lead_vertex.y - vertex.y).abs();
@@ -128,7 +136,7 @@ fn move_rope(rope: &mut Rope, movement: &Movement) {
 }
 
 /// Apply a set of movements to the specified rope
-pub fn process_movements(rope: &mut Rope, movements: &Vec<Movement>, history: &mut HashSet<RopeVertex>) {
+pub fn process_movements(rope: &mut Rope, movements: &[Movement], history: &mut HashSet<RopeVertex>) {
     for movement in movements {
         //  break each movement into smaller movements of distance 1
         let lil_movement = Movement { direction: movement.direction, distance: 1};
@@ -145,6 +153,35 @@ pub fn process_movements(rope: &mut Rope, movements: &Vec<Movement>, history: &m
     }
 }
 
+/// Solution for day 9
+pub struct Day9 {
+    movements: Vec<Movement>,
+}
+
+impl Day9 {
+    /// Count the positions visited by the tail of a rope with the specified number of vertices
+    fn tail_positions(&self, num_vertices: u32) -> usize {
+        let mut rope: Rope = Rope::new(num_vertices);
+        let mut history: HashSet<RopeVertex> = HashSet::new();
+        process_movements(&mut rope, &self.movements, &mut history);
+        return history.len();
+    }
+}
+
+impl Solution for Day9 {
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> {
+        Ok(Self { movements: read_movements(reader)? })
+    }
+
+    fn part1(&self) -> Answer {
+        Box::new(self.tail_positions(2))
+    }
+
+    fn part2(&self) -> Answer {
+        Box::new(self.tail_positions(10))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,8 +189,7 @@ mod tests {
     #[test]
     fn move_rope_up() {
         let mut rope: Rope = Rope::new(5);
-        let mut movements: Vec<Movement> = Vec::new();
-        movements.push(Movement { direction: Direction::Up, distance: 4});
+        let movements: Vec<Movement> = vec![Movement { direction: Direction::Up, distance: 4}];
 
         let mut history: HashSet<RopeVertex> = HashSet::new();
         process_movements(&mut rope, &movements, &mut history);
@@ -168,8 +204,7 @@ mod tests {
     #[test]
     fn move_rope_down() {
         let mut rope: Rope = Rope::new(5);
-        let mut movements: Vec<Movement> = Vec::new();
-        movements.push(Movement { direction: Direction::Down, distance: 4});
+        let movements: Vec<Movement> = vec![Movement { direction: Direction::Down, distance: 4}];
 
         let mut history: HashSet<RopeVertex> = HashSet::new();
         process_movements(&mut rope, &movements, &mut history);
@@ -184,8 +219,7 @@ mod tests {
     #[test]
     fn move_rope_left() {
         let mut rope: Rope = Rope::new(5);
-        let mut movements: Vec<Movement> = Vec::new();
-        movements.push(Movement { direction: Direction::Left, distance: 4});
+        let movements: Vec<Movement> = vec![Movement { direction: Direction::Left, distance: 4}];
 
         let mut history: HashSet<RopeVertex> = HashSet::new();
         process_movements(&mut rope, &movements, &mut history);
@@ -200,8 +234,7 @@ mod tests {
     #[test]
     fn move_rope_right() {
         let mut rope: Rope = Rope::new(5);
-        let mut movements: Vec<Movement> = Vec::new();
-        movements.push(Movement { direction: Direction::Right, distance: 4});
+        let movements: Vec<Movement> = vec![Movement { direction: Direction::Right, distance: 4}];
 
         let mut history: HashSet<RopeVertex> = HashSet::new();
         process_movements(&mut rope, &movements, &mut history);
@@ -217,7 +250,7 @@ mod tests {
     fn r5() {
         let input = "R 5";
         let mut buf = input.as_bytes();
-        let movements = read_movements(&mut buf);
+        let movements = read_movements(&mut buf).unwrap();
         assert_eq!(1, movements.len());
 
         let mut rope: Rope = Rope::new(10);
@@ -241,7 +274,7 @@ mod tests {
         let input = "R 5
             U 8";
         let mut buf = input.as_bytes();
-        let movements = read_movements(&mut buf);
+        let movements = read_movements(&mut buf).unwrap();
         assert_eq!(2, movements.len());
 
         let mut rope: Rope = Rope::new(10);
@@ -271,7 +304,7 @@ mod tests {
             L 5
             R 2";
         let mut buf = input.as_bytes();
-        let movements = read_movements(&mut buf);
+        let movements = read_movements(&mut buf).unwrap();
         assert_eq!(8, movements.len());
 
         assert_eq!(Movement { direction: Direction::Right, distance: 4}, movements[0]);
@@ -311,7 +344,7 @@ mod tests {
             L 25
             U 20";
         let mut buf = input.as_bytes();
-        let movements = read_movements(&mut buf);
+        let movements = read_movements(&mut buf).unwrap();
         assert_eq!(8, movements.len());
 
         let mut rope: Rope = Rope::new(10);
@@ -322,4 +355,29 @@ mod tests {
         let visited_positions = history.len();
         assert_eq!(36, visited_positions);
     }
+
+    #[test]
+    fn invalid_movements() {
+        let input = "R 4
+            X 4";
+        let mut buf = input.as_bytes();
+        let err = read_movements(&mut buf).unwrap_err();
+        assert_eq!(9, err.day);
+        assert_eq!(2, err.line);
+        assert_eq!(13, err.column);
+        assert_eq!("X", err.text);
+
+        let input = "R -4";
+        let mut buf = input.as_bytes();
+        let err = read_movements(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!(3, err.column);
+        assert_eq!("-4", err.text);
+
+        let input = "R";
+        let mut buf = input.as_bytes();
+        let err = read_movements(&mut buf).unwrap_err();
+        assert_eq!(1, err.line);
+        assert_eq!("R", err.text);
+    }
 }
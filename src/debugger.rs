@@ -0,0 +1,412 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::day10::{CycleState, FakeComputer, Instruction, Register, REGISTERS, REGISTER_COUNT};
+
+/// The most cycles `continue` will run before giving up, in case the program loops
+/// forever without hitting a breakpoint
+pub const CONTINUE_LIMIT: usize = 1_000_000;
+
+/// The most cycles the debugger can step back through.  Older snapshots are
+/// dropped, so a long `continue` doesn't keep a snapshot of every cycle it ran.
+pub const HISTORY_LIMIT: usize = 10_000;
+
+/// A comparison used in a register breakpoint
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn from_str(s: &str) -> Option<Self> {
+        match s {
+            "==" => Some(Self::Eq),
+            "!=" => Some(Self::Ne),
+            "<" => Some(Self::Lt),
+            "<=" => Some(Self::Le),
+            ">" => Some(Self::Gt),
+            ">=" => Some(Self::Ge),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        }
+    }
+
+    fn test(self, a: i32, b: i32) -> bool {
+        match self {
+            Self::Eq => a == b,
+            Self::Ne => a != b,
+            Self::Lt => a < b,
+            Self::Le => a <= b,
+            Self::Gt => a > b,
+            Self::Ge => a >= b,
+        }
+    }
+}
+
+/// A condition that stops the program when it's about to run a cycle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Breakpoint {
+    /// Stop before the specified cycle
+    Cycle(i32),
+    /// Stop before the instruction with the specified index starts
+    Instruction(usize),
+    /// Stop when a register compares true with a value
+    Register(Register, Comparison, i32),
+}
+
+impl Breakpoint {
+    /// Parse a breakpoint: "cycle N", "ip N" or "REG OP VALUE", e.g. "x >= 10"
+    pub fn parse(args: &[&str]) -> Result<Self, String> {
+        let number = |s: &str| s.parse::<i32>().map_err(|_| format!("invalid number {s}"));
+
+        match args {
+            ["cycle", n] => Ok(Self::Cycle(number(n)?)),
+            ["ip", n] => Ok(Self::Instruction(n.parse::<usize>().map_err(|_| format!("invalid instruction index {n}"))?)),
+            [reg, op, n] => {
+                let register = Register::from_name(reg).ok_or_else(|| format!("unknown register {reg}"))?;
+                let comparison = Comparison::from_str(op).ok_or_else(|| format!("unknown comparison {op}"))?;
+                Ok(Self::Register(register, comparison, number(n)?))
+            },
+            _ => Err("usage: break cycle N | break ip N | break REG OP VALUE".to_string()),
+        }
+    }
+
+    /// Whether the breakpoint is hit by the computer's current state
+    fn hit(&self, cpu: &FakeComputer) -> bool {
+        match *self {
+            Self::Cycle(c) => cpu.cycle == c,
+            Self::Instruction(i) => cpu.ip == i && cpu.ic == 0,
+            Self::Register(r, cmp, v) => cmp.test(cpu.register(r), v),
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Cycle(c) => write!(f, "cycle {c}"),
+            Self::Instruction(i) => write!(f, "ip {i}"),
+            Self::Register(r, cmp, v) => write!(f, "{r} {} {v}", cmp.as_str()),
+        }
+    }
+}
+
+/// Why the debugger stopped running the program
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StopReason {
+    /// A breakpoint was hit (the index of the breakpoint)
+    Breakpoint(usize),
+    /// The end of the program was reached
+    Finished,
+    /// The cycle limit was reached
+    Limit,
+}
+
+/// The registers of the computer at some point, so it can be returned there
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Snapshot {
+    cycle: i32,
+    ip: usize,
+    ic: usize,
+    registers: [i32; REGISTER_COUNT],
+}
+
+/// A debugger for the day 10 CPU, supporting breakpoints, stepping forwards and
+/// backwards, and inspection of the registers and program
+pub struct Debugger {
+    cpu: FakeComputer,
+    breakpoints: Vec<Breakpoint>,
+    /// Snapshots from before each of the last HISTORY_LIMIT cycles, oldest first
+    history: VecDeque<Snapshot>,
+}
+
+impl Debugger {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self { cpu: FakeComputer::new(program), breakpoints: Vec::new(), history: VecDeque::new() }
+    }
+
+    /// Return the computer being debugged
+    pub fn cpu(&self) -> &FakeComputer {
+        &self.cpu
+    }
+
+    /// Return the breakpoints, in the order they were added
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Add a breakpoint, returning its index
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        return self.breakpoints.len() - 1;
+    }
+
+    /// Remove a breakpoint by index.  Later breakpoints move down to fill the gap.
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        if index < self.breakpoints.len() {
+            return Some(self.breakpoints.remove(index));
+        }
+        return None;
+    }
+
+    /// Whether the program has finished
+    pub fn finished(&self) -> bool {
+        self.cpu.halted()
+    }
+
+    /// Run a single cycle, or return None if the program has finished
+    pub fn step(&mut self) -> Option<CycleState> {
+        if self.finished() {
+            return None;
+        }
+
+        if self.history.len() == HISTORY_LIMIT {
+            self.history.pop_front();
+        }
+        let cpu = &self.cpu;
+        self.history.push_back(Snapshot { cycle: cpu.cycle, ip: cpu.ip, ic: cpu.ic, registers: cpu.registers });
+        return self.cpu.step();
+    }
+
+    /// Go back one cycle, returning false if already at the start or the cycle is
+    /// more than HISTORY_LIMIT cycles ago
+    pub fn step_back(&mut self) -> bool {
+        match self.history.pop_back() {
+            Some(snapshot) => {
+                self.cpu.cycle = snapshot.cycle;
+                self.cpu.ip = snapshot.ip;
+                self.cpu.ic = snapshot.ic;
+                self.cpu.registers = snapshot.registers;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Run until a breakpoint is hit, the program finishes or `limit` cycles have
+    /// been run.  At least one cycle is run, so continuing from a breakpoint
+    /// doesn't stop at it again straight away.
+    pub fn resume(&mut self, limit: usize) -> StopReason {
+        for _ in 0..limit {
+            if self.step().is_none() {
+                return StopReason::Finished;
+            }
+
+            if let Some(i) = self.breakpoints.iter().position(|b| b.hit(&self.cpu)) {
+                return StopReason::Breakpoint(i);
+            }
+        }
+
+        if self.finished() {
+            return StopReason::Finished;
+        }
+        return StopReason::Limit;
+    }
+
+    /// Describe the registers
+    pub fn registers(&self) -> String {
+        let cpu = &self.cpu;
+        let mut description = format!("cycle {} ip {} ic {}", cpu.cycle, cpu.ip, cpu.ic);
+        for spec in &REGISTERS {
+            description.push_str(&format!(" {} {}", spec.name, cpu.register(spec.register)));
+        }
+        return description;
+    }
+
+    /// List the program, marking the current instruction
+    pub fn listing(&self) -> String {
+        let mut listing = String::new();
+        for (i, instruction) in self.cpu.program.iter().enumerate() {
+            let marker = if i == self.cpu.ip { "=>" } else { "  " };
+            listing.push_str(&format!("{marker} {i:4}  {}\n", instruction));
+        }
+        return listing;
+    }
+
+    /// Run a debugger command, returning the output to show
+    pub fn execute(&mut self, line: &str) -> String {
+        let args = line.split_whitespace().collect::<Vec<&str>>();
+        let count = |args: &[&str]| -> Result<usize, String> {
+            match args {
+                [] => Ok(1),
+                [n] => n.parse::<usize>().map_err(|_| format!("invalid count {n}")),
+                _ => Err("expected a count".to_string()),
+            }
+        };
+
+        let result = match args.as_slice() {
+            [] => Ok(String::new()),
+            ["s" | "step", rest @ ..] => count(rest).map(|n| {
+                let stepped = (0..n).take_while(|_| self.step().is_some()).count();
+                if stepped < n {
+                    format!("program finished\n{}", self.registers())
+                } else {
+                    self.registers()
+                }
+            }),
+            ["b" | "back", rest @ ..] => count(rest).map(|n| {
+                let stepped = (0..n).take_while(|_| self.step_back()).count();
+                if stepped < n && self.cpu.cycle == 1 {
+                    format!("at the start\n{}", self.registers())
+                } else if stepped < n {
+                    format!("no earlier history\n{}", self.registers())
+                } else {
+                    self.registers()
+                }
+            }),
+            ["c" | "continue"] => {
+                let reason = match self.resume(CONTINUE_LIMIT) {
+                    StopReason::Breakpoint(i) => format!("breakpoint {i}: {}", self.breakpoints[i]),
+                    StopReason::Finished => "program finished".to_string(),
+                    StopReason::Limit => format!("stopped after {CONTINUE_LIMIT} cycles"),
+                };
+                Ok(format!("{reason}\n{}", self.registers()))
+            },
+            ["break", rest @ ..] => Breakpoint::parse(rest).map(|b| {
+                let i = self.add_breakpoint(b);
+                format!("breakpoint {i}: {b}")
+            }),
+            ["delete", n] => match n.parse::<usize>().ok().and_then(|i| self.remove_breakpoint(i)) {
+                Some(b) => Ok(format!("deleted breakpoint {n}: {b}")),
+                None => Err(format!("no breakpoint {n}")),
+            },
+            ["breakpoints"] => Ok(self.breakpoints.iter().enumerate()
+                .map(|(i, b)| format!("{i}: {b}"))
+                .collect::<Vec<String>>()
+                .join("\n")),
+            ["r" | "regs"] => Ok(self.registers()),
+            ["l" | "list"] => Ok(self.listing().trim_end().to_string()),
+            ["h" | "help"] => Ok(HELP.to_string()),
+            _ => Err(format!("unknown command: {line}")),
+        };
+
+        match result {
+            Ok(output) => output,
+            Err(e) => format!("error: {e}"),
+        }
+    }
+}
+
+/// Help for the debugger commands
+pub const HELP: &str = "\
+step [N], s      run N cycles (default 1)
+back [N], b      go back N cycles (default 1), up to 10000 cycles ago
+continue, c      run until a breakpoint is hit or the program finishes
+break cycle N    stop before cycle N
+break ip N       stop before instruction N starts
+break REG OP N   stop when a register compares true, e.g. break x >= 10
+delete N         delete breakpoint N
+breakpoints      list the breakpoints
+regs, r          show the registers
+list, l          list the program
+quit, q          exit";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day10::assemble;
+
+    const PROGRAM: &str = "noop
+        addx 3
+        addx -5
+        addy 2
+        noop";
+
+    #[test]
+    fn breakpoints() {
+        let mut dbg = Debugger::new(assemble(PROGRAM).unwrap());
+        assert_eq!(0, dbg.add_breakpoint(Breakpoint::Cycle(3)));
+        assert_eq!(1, dbg.add_breakpoint(Breakpoint::Instruction(2)));
+        assert_eq!(2, dbg.add_breakpoint(Breakpoint::Register(Register::X, Comparison::Lt, 0)));
+
+        assert_eq!(StopReason::Breakpoint(0), dbg.resume(CONTINUE_LIMIT));
+        assert_eq!((3, 1), (dbg.cpu().cycle, dbg.cpu().ip));
+
+        assert_eq!(StopReason::Breakpoint(1), dbg.resume(CONTINUE_LIMIT));
+        assert_eq!((4, 2, 4), (dbg.cpu().cycle, dbg.cpu().ip, dbg.cpu().x()));
+
+        assert_eq!(StopReason::Breakpoint(2), dbg.resume(CONTINUE_LIMIT));
+        assert_eq!((6, -1), (dbg.cpu().cycle, dbg.cpu().x()));
+
+        //  x stays negative, so every cycle hits the breakpoint
+        assert_eq!(Some(Breakpoint::Register(Register::X, Comparison::Lt, 0)), dbg.remove_breakpoint(2));
+        assert_eq!(None, dbg.remove_breakpoint(2));
+        assert_eq!(StopReason::Finished, dbg.resume(CONTINUE_LIMIT));
+        assert_eq!((9, 2), (dbg.cpu().cycle, dbg.cpu().register(Register::Y)));
+        assert_eq!(None, dbg.step());
+    }
+
+    #[test]
+    fn stepping() {
+        let mut dbg = Debugger::new(assemble("loop: addx 1\njmp loop").unwrap());
+        assert!(!dbg.step_back());
+
+        let state = dbg.step().unwrap();
+        assert_eq!((1, 1), (state.cycle, state.x_after));
+        dbg.step();
+        assert_eq!(2, dbg.cpu().x());
+
+        assert!(dbg.step_back());
+        assert_eq!((2, 0, 1, 1), (dbg.cpu().cycle, dbg.cpu().ip, dbg.cpu().ic, dbg.cpu().x()));
+
+        //  The program never finishes
+        assert_eq!(StopReason::Limit, dbg.resume(100));
+        assert_eq!(102, dbg.cpu().cycle);
+        for _ in 0..101 {
+            assert!(dbg.step_back());
+        }
+        assert!(!dbg.step_back());
+        assert_eq!("cycle 1 ip 0 ic 0 x 1 y 0", dbg.registers());
+
+        //  Only the last HISTORY_LIMIT cycles can be stepped back through
+        assert_eq!(StopReason::Limit, dbg.resume(HISTORY_LIMIT + 10));
+        assert_eq!(HISTORY_LIMIT, dbg.history.len());
+        for _ in 0..HISTORY_LIMIT {
+            assert!(dbg.step_back());
+        }
+        assert!(!dbg.step_back());
+        assert_eq!(11, dbg.cpu().cycle);
+        assert_eq!("no earlier history\ncycle 11 ip 0 ic 1 x 4 y 0", dbg.execute("back"));
+    }
+
+    #[test]
+    fn commands() {
+        let mut dbg = Debugger::new(assemble(PROGRAM).unwrap());
+
+        assert_eq!("breakpoint 0: x < 0", dbg.execute("break x < 0"));
+        assert_eq!("breakpoint 1: ip 3", dbg.execute("break ip 3"));
+        assert_eq!("breakpoint 2: cycle 2", dbg.execute("break cycle 2"));
+        assert_eq!("0: x < 0\n1: ip 3\n2: cycle 2", dbg.execute("breakpoints"));
+        assert_eq!("deleted breakpoint 2: cycle 2", dbg.execute("delete 2"));
+
+        assert_eq!("cycle 3 ip 1 ic 1 x 1 y 0", dbg.execute("step 2"));
+        assert_eq!("breakpoint 0: x < 0\ncycle 6 ip 3 ic 0 x -1 y 0", dbg.execute("continue"));
+        assert_eq!("cycle 5 ip 2 ic 1 x 4 y 0", dbg.execute("back"));
+        assert_eq!("cycle 5 ip 2 ic 1 x 4 y 0", dbg.execute("r"));
+        assert_eq!("      0  noop\n      1  addx 3\n=>    2  addx -5\n      3  addy 2\n      4  noop", dbg.execute("list"));
+        assert_eq!("program finished\ncycle 9 ip 5 ic 0 x -1 y 2", dbg.execute("s 10"));
+        assert_eq!("at the start\ncycle 1 ip 0 ic 0 x 1 y 0", dbg.execute("b 10"));
+
+        assert_eq!("error: unknown command: frobnicate", dbg.execute("frobnicate"));
+        assert_eq!("error: invalid count x", dbg.execute("step x"));
+        assert_eq!("error: unknown register z", dbg.execute("break z > 1"));
+        assert_eq!("error: unknown comparison =", dbg.execute("break x = 1"));
+        assert_eq!("error: no breakpoint 5", dbg.execute("delete 5"));
+        assert_eq!("", dbg.execute(""));
+    }
+}
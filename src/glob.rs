@@ -0,0 +1,177 @@
+/// A part of a glob pattern
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// A character that must match exactly
+    Literal(char),
+    /// `?`, any single character other than '/'
+    AnyChar,
+    /// `*`, any number of characters other than '/'
+    Star,
+    /// `**/`, any number of whole path components (including none)
+    Components,
+    /// `**` anywhere else, any number of any characters
+    Anything,
+    /// `[...]`, any character in (or with `[!...]`, not in) a set of ranges
+    Class(Vec<(char, char)>, bool),
+}
+
+/// A shell-style glob pattern for matching paths.  `?` matches a single character
+/// and `*` any number of characters, but neither matches '/'.  `**/` matches any
+/// number of directories and `**` on its own matches anything.  `[abc]`, `[a-z]`
+/// and `[!abc]` match a character from (or not from) a set.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    /// Compile a glob pattern.  A `[` without a matching `]` is taken literally.
+    pub fn new(pattern: &str) -> Self {
+        let chars = pattern.chars().collect::<Vec<char>>();
+        let mut tokens = Vec::new();
+
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                '?' => tokens.push(Token::AnyChar),
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    if chars.get(i + 2) == Some(&'/') {
+                        tokens.push(Token::Components);
+                        i += 2;
+                    } else {
+                        tokens.push(Token::Anything);
+                        i += 1;
+                    }
+                },
+                '*' => tokens.push(Token::Star),
+                '[' => {
+                    match Self::class(&chars[i + 1..]) {
+                        Some((token, len)) => {
+                            tokens.push(token);
+                            i += len;
+                        },
+                        None => tokens.push(Token::Literal('[')),
+                    }
+                },
+                c => tokens.push(Token::Literal(c)),
+            }
+            i += 1;
+        }
+
+        return Self { tokens };
+    }
+
+    /// Parse a character class from the text after its `[`, returning the token and
+    /// the number of characters used (including the `]`)
+    fn class(chars: &[char]) -> Option<(Token, usize)> {
+        let negated = chars.first() == Some(&'!');
+        let mut i = if negated { 1 } else { 0 };
+        let mut ranges = Vec::new();
+
+        //  A ']' straight after the '[' (or '[!') is part of the set
+        let start = i;
+        while i < chars.len() && (chars[i] != ']' || i == start) {
+            if i + 2 < chars.len() && chars[i + 1] == '-' && chars[i + 2] != ']' {
+                ranges.push((chars[i], chars[i + 2]));
+                i += 3;
+            } else {
+                ranges.push((chars[i], chars[i]));
+                i += 1;
+            }
+        }
+
+        if i == chars.len() {
+            return None;
+        }
+
+        return Some((Token::Class(ranges, negated), i + 1));
+    }
+
+    /// Whether the pattern matches the whole of the specified text
+    pub fn matches(&self, text: &str) -> bool {
+        let chars = text.chars().collect::<Vec<char>>();
+
+        //  matched[t][c]: whether tokens[t..] match chars[c..]
+        let mut matched = vec![vec![false; chars.len() + 1]; self.tokens.len() + 1];
+        matched[self.tokens.len()][chars.len()] = true;
+
+        for t in (0..self.tokens.len()).rev() {
+            for c in (0..=chars.len()).rev() {
+                let next = chars.get(c);
+                matched[t][c] = match &self.tokens[t] {
+                    Token::Literal(l) => next == Some(l) && matched[t + 1][c + 1],
+                    Token::AnyChar => next.is_some_and(|n| *n != '/') && matched[t + 1][c + 1],
+                    Token::Star => matched[t + 1][c] || (next.is_some_and(|n| *n != '/') && matched[t][c + 1]),
+                    Token::Anything => matched[t + 1][c] || (next.is_some() && matched[t][c + 1]),
+                    Token::Components => {
+                        //  Either no more components, or a component and its '/'
+                        matched[t + 1][c] || chars[c..].iter().position(|n| *n == '/')
+                            .is_some_and(|slash| slash > 0 && matched[t][c + slash + 1])
+                    },
+                    Token::Class(ranges, negated) => {
+                        next.is_some_and(|n| *n != '/' && ranges.iter().any(|(lo, hi)| lo <= n && n <= hi) != *negated)
+                            && matched[t + 1][c + 1]
+                    },
+                };
+            }
+        }
+
+        return matched[0][0];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        let g = Glob::new("/a/*.txt");
+        assert!(g.matches("/a/b.txt"));
+        assert!(g.matches("/a/.txt"));
+        assert!(!g.matches("/a/b/c.txt"));
+        assert!(!g.matches("/a/b.txt.bak"));
+
+        let g = Glob::new("/?/?");
+        assert!(g.matches("/a/e"));
+        assert!(!g.matches("/a/ee"));
+        assert!(!g.matches("//e"));
+
+        assert!(Glob::new("*").matches("abc"));
+        assert!(Glob::new("*").matches(""));
+        assert!(!Glob::new("*").matches("a/b"));
+    }
+
+    #[test]
+    fn double_star() {
+        let g = Glob::new("/**/e");
+        assert!(g.matches("/e"));
+        assert!(g.matches("/a/e"));
+        assert!(g.matches("/a/b/c/e"));
+        assert!(!g.matches("/a/be"));
+
+        let g = Glob::new("/a/**");
+        assert!(g.matches("/a/"));
+        assert!(g.matches("/a/b/c"));
+        assert!(!g.matches("/b/c"));
+
+        assert!(Glob::new("**").matches("/"));
+        assert!(Glob::new("/**.lst").matches("/a/h.lst"));
+    }
+
+    #[test]
+    fn classes() {
+        let g = Glob::new("/[a-c]/[!x]");
+        assert!(g.matches("/b/y"));
+        assert!(!g.matches("/d/y"));
+        assert!(!g.matches("/a/x"));
+        assert!(!g.matches("/a//"));
+
+        assert!(Glob::new("[]]").matches("]"));
+        assert!(Glob::new("[a-]").matches("-"));
+
+        //  An unterminated class is literal
+        assert!(Glob::new("a[b").matches("a[b"));
+        assert!(!Glob::new("a[b").matches("ab"));
+    }
+}
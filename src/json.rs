@@ -0,0 +1,355 @@
+use crate::common::ParseError;
+
+/// A JSON value
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    /// A number, kept as it was written so the caller can decide how to read it
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    /// An object, with its members in the order they were written
+    Object(Vec<(String, Json)>),
+}
+
+/// A JSON value along with its (byte) position in the text it was parsed from, so
+/// that errors found after parsing can still point at the right place
+#[derive(Clone, Debug, PartialEq)]
+pub struct Json {
+    pub pos: usize,
+    pub value: JsonValue,
+}
+
+impl Json {
+    /// Return the member of an object with the specified name
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match &self.value {
+            JsonValue::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Create an error for position `pos` within `text`, working out the line and
+/// column and using the rest of that line as the offending text
+pub fn error_at(day: u32, text: &str, pos: usize, message: &str) -> ParseError {
+    let pos = pos.min(text.len());
+    let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = text[pos..].find('\n').map_or(text.len(), |i| pos + i);
+    let line_num = text[..pos].matches('\n').count() + 1;
+    let column = text[line_start..pos].chars().count() + 1;
+
+    return ParseError::new(day, line_num, column, text[pos..line_end].trim_end(), message);
+}
+
+/// Quote and escape a string for use in JSON
+pub fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    return quoted;
+}
+
+/// The deepest arrays and objects can be nested, so that parsing a document can't
+/// overflow the stack
+pub const MAX_DEPTH: usize = 256;
+
+/// Parse a JSON document.  Errors are reported against the specified day.
+pub fn parse(day: u32, text: &str) -> Result<Json, ParseError> {
+    let mut parser = Parser { day, text, pos: 0, depth: 0 };
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected text after JSON value"));
+    }
+
+    return Ok(value);
+}
+
+/// A recursive descent JSON parser
+struct Parser<'a> {
+    day: u32,
+    text: &'a str,
+    pos: usize,
+    /// The number of arrays and objects currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> ParseError {
+        error_at(self.day, self.text, self.pos, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    /// Consume the expected character, after any whitespace
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected '{expected}'")));
+        }
+        self.pos += 1;
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<Json, ParseError> {
+        self.skip_whitespace();
+        let pos = self.pos;
+
+        let value = match self.peek() {
+            Some('{' | '[') if self.depth == MAX_DEPTH => return Err(self.error("nesting too deep")),
+            Some('{') => self.nested(Self::object)?,
+            Some('[') => self.nested(Self::array)?,
+            Some('"') => JsonValue::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            Some(_) => self.literal()?,
+            None => return Err(self.error("unexpected end of input")),
+        };
+
+        return Ok(Json { pos, value });
+    }
+
+    /// Parse an array or object, one level deeper
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<JsonValue, ParseError>) -> Result<JsonValue, ParseError> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        return value;
+    }
+
+    fn object(&mut self) -> Result<JsonValue, ParseError> {
+        let mut members = Vec::new();
+        self.expect('{')?;
+
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a member name"));
+            }
+            let key = self.string()?;
+            self.expect(':')?;
+            members.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Object(members));
+                },
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, ParseError> {
+        let mut elements = Vec::new();
+        self.expect('[')?;
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(elements));
+                },
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let mut s = String::new();
+        self.pos += 1;
+
+        loop {
+            let c = match self.peek() {
+                Some(c) => c,
+                None => return Err(self.error("unterminated string")),
+            };
+
+            match c {
+                '"' => {
+                    self.pos += 1;
+                    return Ok(s);
+                },
+                '\\' => {
+                    self.pos += 1;
+                    let escaped = match self.peek() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = self.hex_escape(self.pos + 1)?;
+                            self.pos += 4;
+
+                            //  Characters outside the BMP are written as a UTF-16 surrogate pair
+                            if (0xD800..0xDC00).contains(&code) && self.text[self.pos + 1..].starts_with("\\u") {
+                                let low = self.hex_escape(self.pos + 3)?;
+                                if (0xDC00..0xE000).contains(&low) {
+                                    code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                                    self.pos += 6;
+                                }
+                            }
+
+                            char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+                        },
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    s.push(escaped);
+                    self.pos += 1;
+                },
+                c if (c as u32) < 0x20 => return Err(self.error("control character in string")),
+                c => {
+                    s.push(c);
+                    self.pos += c.len_utf8();
+                },
+            }
+        }
+    }
+
+    /// Read the four hex digits of a `\\u` escape starting at the specified position
+    fn hex_escape(&self, pos: usize) -> Result<u32, ParseError> {
+        let hex = self.text.get(pos..pos + 4).unwrap_or("");
+        return u32::from_str_radix(hex, 16).ok()
+            .filter(|_| hex.len() == 4 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"));
+    }
+
+    fn number(&mut self) -> Result<JsonValue, ParseError> {
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_digit() || "+-.eE".contains(c)) {
+                break;
+            }
+            self.pos += 1;
+        }
+
+        let number = &self.text[start..self.pos];
+        if number.parse::<f64>().is_err() {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+
+        return Ok(JsonValue::Number(number.to_string()));
+    }
+
+    fn literal(&mut self) -> Result<JsonValue, ParseError> {
+        for (word, value) in [("null", JsonValue::Null), ("true", JsonValue::Bool(true)), ("false", JsonValue::Bool(false))] {
+            if self.text[self.pos..].starts_with(word) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+
+        return Err(self.error("unexpected character"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_values() {
+        let json = parse(0, r#" { "a": [1, -2.5e3, "x\"A\n"], "b": {}, "c": [], "d": null, "e": true } "#).unwrap();
+        assert_eq!(1, json.pos);
+
+        let a = json.get("a").unwrap();
+        assert_eq!(8, a.pos);
+        match &a.value {
+            JsonValue::Array(elements) => {
+                assert_eq!(JsonValue::Number("1".to_string()), elements[0].value);
+                assert_eq!(JsonValue::Number("-2.5e3".to_string()), elements[1].value);
+                assert_eq!(JsonValue::String("x\"A\n".to_string()), elements[2].value);
+            },
+            _ => panic!("expected an array"),
+        }
+
+        assert_eq!(JsonValue::Object(vec![]), json.get("b").unwrap().value);
+        assert_eq!(JsonValue::Array(vec![]), json.get("c").unwrap().value);
+        assert_eq!(JsonValue::Null, json.get("d").unwrap().value);
+        assert_eq!(JsonValue::Bool(true), json.get("e").unwrap().value);
+        assert!(json.get("f").is_none());
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        assert_eq!(JsonValue::String("\u{e9}\u{1f600}!".to_string()), parse(0, r#""\u00e9\ud83d\ude00\u0021""#).unwrap().value);
+        assert_eq!(JsonValue::String("\u{1f600}".to_string()), parse(0, r#""\uD83D\uDE00""#).unwrap().value);
+    }
+
+    #[test]
+    fn quote_round_trip() {
+        let s = "a \"quoted\"\\ name\twith\u{1} tabs";
+        let json = parse(0, &quote(s)).unwrap();
+        assert_eq!(JsonValue::String(s.to_string()), json.value);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse(7, "{\n  \"a\": [1,\n  2 3]\n}").unwrap_err();
+        assert_eq!(7, err.day);
+        assert_eq!(3, err.line);
+        assert_eq!(5, err.column);
+        assert_eq!("3]", err.text);
+        assert_eq!("expected ',' or ']'", err.message);
+
+        assert_eq!("unterminated string", parse(0, "\"abc").unwrap_err().message);
+        assert_eq!("invalid unicode escape", parse(0, r#""\ud83d""#).unwrap_err().message);
+        assert_eq!("invalid unicode escape", parse(0, r#""\ud83d\u0041""#).unwrap_err().message);
+        assert_eq!("invalid unicode escape", parse(0, r#""\u12g4""#).unwrap_err().message);
+        assert_eq!("unexpected end of input", parse(0, "[1,").unwrap_err().message);
+        assert_eq!("unexpected character", parse(0, "nope").unwrap_err().message);
+        assert_eq!("invalid number", parse(0, "1.2.3").unwrap_err().message);
+        assert_eq!("unexpected text after JSON value", parse(0, "{} {}").unwrap_err().message);
+
+        let nested = format!("{}{}", "[".repeat(MAX_DEPTH), "]".repeat(MAX_DEPTH));
+        assert!(parse(0, &nested).is_ok());
+        let err = parse(0, &format!("{{\"a\": {}", "[".repeat(300_000))).unwrap_err();
+        assert_eq!((6 + MAX_DEPTH, "nesting too deep"), (err.column, err.message.as_str()));
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fmt::Display, io::BufRead};
+
+use crate::common::ParseError;
+use crate::{day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11};
+
+/// A displayable puzzle answer
+pub type Answer = Box<dyn Display>;
+
+/// A common interface to the solution for each day.  A solution is created by
+/// parsing the puzzle input, after which either part can be solved.
+pub trait Solution {
+    /// Read the puzzle input
+    fn parse(reader: &mut dyn BufRead) -> Result<Self, ParseError> where Self: Sized;
+
+    /// Solve part 1 of the puzzle
+    fn part1(&self) -> Answer;
+
+    /// Solve part 2 of the puzzle
+    fn part2(&self) -> Answer;
+}
+
+/// A function that parses input for a particular day into a boxed solution
+pub type Parser = fn(&mut dyn BufRead) -> Result<Box<dyn Solution>, ParseError>;
+
+fn parse_boxed<S: Solution + 'static>(reader: &mut dyn BufRead) -> Result<Box<dyn Solution>, ParseError> {
+    return Ok(Box::new(S::parse(reader)?));
+}
+
+/// The registry of solutions, keyed by day number
+const REGISTRY: [(u32, Parser); 11] = [
+    (1, parse_boxed::<day1::Day1>),
+    (2, parse_boxed::<day2::Day2>),
+    (3, parse_boxed::<day3::Day3>),
+    (4, parse_boxed::<day4::Day4>),
+    (5, parse_boxed::<day5::Day5>),
+    (6, parse_boxed::<day6::Day6>),
+    (7, parse_boxed::<day7::Day7>),
+    (8, parse_boxed::<day8::Day8>),
+    (9, parse_boxed::<day9::Day9>),
+    (10, parse_boxed::<day10::Day10>),
+    (11, parse_boxed::<day11::Day11>),
+];
+
+/// Return the days for which a solution is available, in ascending order
+pub fn days() -> Vec<u32> {
+    return REGISTRY.iter().map(|(day, _)| *day).collect();
+}
+
+/// Return the input parser for the specified day, if there is a solution for it
+pub fn parser(day: u32) -> Option<Parser> {
+    return REGISTRY.iter().find(|(d, _)| *d == day).map(|(_, p)| *p);
+}
+
+/// Parse the input for the specified day.  Returns None if there is no solution
+/// for that day.
+pub fn parse(day: u32, reader: &mut dyn BufRead) -> Option<Result<Box<dyn Solution>, ParseError>> {
+    return parser(day).map(|p| p(reader));
+}
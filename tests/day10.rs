@@ -7,7 +7,7 @@ use std::fs::File;
 fn day10_part1_example() {
     let f = File::open("data/day10_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let program = read_program(&mut reader);
+    let program = read_program(&mut reader).unwrap();
 
     let mut cpu = FakeComputer::new(program);
 
@@ -15,44 +15,47 @@ fn day10_part1_example() {
     //  want the value *during* the 20th cycle, not *after* it.  Running the machine for
     //  20 cycles would give the value *after* 20 cycles.
     cpu.run(19);
-    assert_eq!(21, cpu.x);
+    assert_eq!(21, cpu.x());
     let ss20 = cpu.get_signal_strength();
     assert_eq!(420, ss20);
 
     cpu.run(40);
-    assert_eq!(19, cpu.x);
+    assert_eq!(19, cpu.x());
     let ss60 = cpu.get_signal_strength();
     assert_eq!(1140, ss60);
     
     cpu.run(40);
-    assert_eq!(18, cpu.x);
+    assert_eq!(18, cpu.x());
     let ss100 = cpu.get_signal_strength();
     assert_eq!(1800, ss100);
 
     cpu.run(40);
-    assert_eq!(21, cpu.x);
+    assert_eq!(21, cpu.x());
     let ss140 = cpu.get_signal_strength();
     assert_eq!(2940, ss140);
 
     cpu.run(40);
-    assert_eq!(16, cpu.x);
+    assert_eq!(16, cpu.x());
     let ss180 = cpu.get_signal_strength();
     assert_eq!(2880, ss180);
 
     cpu.run(40);
-    assert_eq!(18, cpu.x);
+    assert_eq!(18, cpu.x());
     let ss240 = cpu.get_signal_strength();
     assert_eq!(3960, ss240);
 
     let ss_sum = ss20 + ss60 + ss100 + ss140 + ss180 + ss240;
     assert_eq!(13140, ss_sum);
+
+    cpu.reset();
+    assert_eq!(13140, cpu.signal_strength_sum(&[20, 60, 100, 140, 180, 220]));
 }
 
 #[test]
 fn day10_part1_actual() {
     let f = File::open("data/day10_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let program = read_program(&mut reader);
+    let program = read_program(&mut reader).unwrap();
 
     let mut cpu = FakeComputer::new(program);
 
@@ -76,24 +79,58 @@ fn day10_part1_actual() {
 
     let ss_sum = ss20 + ss60 + ss100 + ss140 + ss180 + ss240;
     assert_eq!(14160, ss_sum);
+
+    cpu.reset();
+    assert_eq!(14160, cpu.signal_strength_sum(&[20, 60, 100, 140, 180, 220]));
 }
 
 #[test]
 fn day10_part2_example() {
     let f = File::open("data/day10_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let program = read_program(&mut reader);
+    let program = read_program(&mut reader).unwrap();
     let mut cpu = FakeComputer::new(program);
     cpu.reset();
-    //cpu.render_display();
+
+    let frame = cpu.render_crt();
+    assert_eq!(vec![
+        "##..##..##..##..##..##..##..##..##..##..",
+        "###...###...###...###...###...###...###.",
+        "####....####....####....####....####....",
+        "#####.....#####.....#####.....#####.....",
+        "######......######......######......####",
+        "#######.......#######.......#######.....",
+    ], frame);
+    assert!(ocr(&frame).is_err());
 }
 
 #[test]
 fn day10_part2_actual() {
     let f = File::open("data/day10_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let program = read_program(&mut reader);
+    let program = read_program(&mut reader).unwrap();
     let mut cpu = FakeComputer::new(program);
     cpu.reset();
-    //cpu.render_display();
+
+    let frame = cpu.render_crt();
+    assert_eq!(vec![
+        "###....##.####.###..###..####.####..##..",
+        "#..#....#.#....#..#.#..#.#....#....#..#.",
+        "#..#....#.###..#..#.#..#.###..###..#....",
+        "###.....#.#....###..###..#....#....#....",
+        "#.#..#..#.#....#.#..#....#....#....#..#.",
+        "#..#..##..####.#..#.#....####.#.....##..",
+    ], frame);
+    assert_eq!("RJERPEFC", ocr(&frame).unwrap());
+}
+
+#[test]
+fn day10_disassemble_actual() {
+    let f = File::open("data/day10_actual.txt").unwrap();
+    let mut reader = BufReader::new(f);
+    let program = read_program(&mut reader).unwrap();
+
+    let source = disassemble(&program, false);
+    assert_eq!(std::fs::read_to_string("data/day10_actual.txt").unwrap().trim_end(), source.trim_end());
+    assert_eq!(program, assemble(&disassemble(&program, true)).unwrap());
 }
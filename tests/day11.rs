@@ -7,8 +7,8 @@ use std::fs::File;
 fn day11_part1_example() {
     let f = File::open("data/day11_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let mut monkeys = read_monkeys(&mut reader);
-    play_rounds_p1(&mut monkeys, 20);
+    let mut monkeys = read_monkeys(&mut reader).unwrap();
+    play_rounds_p1(&mut monkeys, 20).unwrap();
     let monkey_business = calc_monkey_business(&monkeys);
     assert_eq!(10605, monkey_business);
 }
@@ -17,8 +17,8 @@ fn day11_part1_example() {
 fn day11_part1_actual() {
     let f = File::open("data/day11_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let mut monkeys = read_monkeys(&mut reader);
-    play_rounds_p1(&mut monkeys, 20);
+    let mut monkeys = read_monkeys(&mut reader).unwrap();
+    play_rounds_p1(&mut monkeys, 20).unwrap();
     let monkey_business = calc_monkey_business(&monkeys);
     assert_eq!(66124, monkey_business);
 }
@@ -27,8 +27,8 @@ fn day11_part1_actual() {
 fn day11_part2_example() {
     let f = File::open("data/day11_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let mut monkeys = read_monkeys(&mut reader);
-    play_rounds_p2(&mut monkeys, 10000);
+    let mut monkeys = read_monkeys(&mut reader).unwrap();
+    play_rounds_p2(&mut monkeys, 10000).unwrap();
     let monkey_business = calc_monkey_business(&monkeys);
     assert_eq!(2713310158, monkey_business);
 }
@@ -37,8 +37,60 @@ fn day11_part2_example() {
 fn day11_part2_actual() {
     let f = File::open("data/day11_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let mut monkeys = read_monkeys(&mut reader);
-    play_rounds_p2(&mut monkeys, 10000);
+    let mut monkeys = read_monkeys(&mut reader).unwrap();
+    play_rounds_p2(&mut monkeys, 10000).unwrap();
     let monkey_business = calc_monkey_business(&monkeys);
-    assert_eq!(2713310158, monkey_business);
+    assert_eq!(19309892877, monkey_business);
+}
+
+#[test]
+fn day11_exact_example() {
+    let f = File::open("data/day11_example.txt").unwrap();
+    let mut reader = BufReader::new(f);
+    let monkeys = read_monkeys(&mut reader).unwrap();
+
+    let mut exact = monkeys.clone();
+    play_rounds_exact(&mut exact, 20, 3).unwrap();
+    assert_eq!(10605, calc_monkey_business(&exact));
+
+    //  Without relief the exact worry levels grow quickly, but they should give the
+    //  same counts as working modulo the LCM of the divisors
+    let mut exact = monkeys.clone();
+    play_rounds_exact(&mut exact, 20, 1).unwrap();
+    let mut reduced = monkeys.clone();
+    play_rounds_p2(&mut reduced, 20).unwrap();
+    assert_eq!(99 * 103, calc_monkey_business(&exact));
+    assert_eq!(99 * 103, calc_monkey_business(&reduced));
+}
+
+#[test]
+fn day11_fast_forward() {
+    for file in ["data/day11_example.txt", "data/day11_actual.txt"] {
+        let f = File::open(file).unwrap();
+        let mut reader = BufReader::new(f);
+        let monkeys = read_monkeys(&mut reader).unwrap();
+
+        //  Skipping cycles should give the same result as playing every round
+        for rounds in [0, 1, 20, 1000, 10000] {
+            let mut slow = monkeys.clone();
+            play_rounds_p2(&mut slow, rounds).unwrap();
+            let mut fast = monkeys.clone();
+            play_rounds_fast(&mut fast, rounds).unwrap();
+
+            for (s, f) in slow.iter().zip(fast.iter()) {
+                assert_eq!(s.items_inspected(), f.items_inspected(), "{file} {rounds}");
+                let mut items = f.items.clone();
+                items.sort();
+                let mut expected = s.items.clone();
+                expected.sort();
+                assert_eq!(expected, items, "{file} {rounds}");
+            }
+        }
+    }
+
+    let f = File::open("data/day11_example.txt").unwrap();
+    let mut reader = BufReader::new(f);
+    let mut monkeys = read_monkeys(&mut reader).unwrap();
+    play_rounds_fast(&mut monkeys, 1_000_000_000_000).unwrap();
+    assert_eq!(27142382301385558311211320, calc_monkey_business(&monkeys));
 }
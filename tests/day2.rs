@@ -7,7 +7,7 @@ use std::fs::File;
 fn day2_part1_example() {
     let f = File::open("data/day2_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let strategy_vector = read_strategy1_vector(&mut reader);
+    let strategy_vector = read_strategy1_vector(&mut reader).unwrap();
     let round_vector = solve_type1(&strategy_vector);
     let score = total_score(&round_vector);
     assert_eq!(15, score);
@@ -17,7 +17,7 @@ fn day2_part1_example() {
 fn day2_part1_actual() {
     let f = File::open("data/day2_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let strategy_vector = read_strategy1_vector(&mut reader);
+    let strategy_vector = read_strategy1_vector(&mut reader).unwrap();
     let round_vector = solve_type1(&strategy_vector);
     let score = total_score(&round_vector);
     assert_eq!(13009, score);
@@ -27,7 +27,7 @@ fn day2_part1_actual() {
 fn day2_part2_example() {
     let f = File::open("data/day2_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let strategy_vector = read_strategy2_vector(&mut reader);
+    let strategy_vector = read_strategy2_vector(&mut reader).unwrap();
     let round_vector = solve_type2(&strategy_vector);
     let score = total_score(&round_vector);
     assert_eq!(12, score);
@@ -36,7 +36,7 @@ fn day2_part2_example() {
 fn day2_part2_actual() {
     let f = File::open("data/day2_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let strategy_vector = read_strategy2_vector(&mut reader);
+    let strategy_vector = read_strategy2_vector(&mut reader).unwrap();
     let round_vector = solve_type2(&strategy_vector);
     let score = total_score(&round_vector);
     assert_eq!(10398, score);
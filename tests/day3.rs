@@ -7,7 +7,7 @@ use std::fs::File;
 fn day3_part1_example() {
     let f = File::open("data/day3_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let rucksack_vector = read_rucksack_vector(&mut reader);
+    let rucksack_vector = read_rucksack_vector(&mut reader).unwrap();
     let sum = error_priority_sum(&rucksack_vector);
     assert_eq!(157, sum);
 }
@@ -16,7 +16,7 @@ fn day3_part1_example() {
 fn day3_part1_actual() {
     let f = File::open("data/day3_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let rucksack_vector = read_rucksack_vector(&mut reader);
+    let rucksack_vector = read_rucksack_vector(&mut reader).unwrap();
     let sum = error_priority_sum(&rucksack_vector);
     assert_eq!(7568, sum);
 }
@@ -25,8 +25,8 @@ fn day3_part1_actual() {
 fn day3_part2_example() {
     let f = File::open("data/day3_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let rucksack_vector = read_rucksack_vector(&mut reader);
-    let sum = badge_priority_sum(&rucksack_vector);
+    let rucksack_vector = read_rucksack_vector(&mut reader).unwrap();
+    let sum = badge_priority_sum(&rucksack_vector).unwrap();
     assert_eq!(70, sum);
 }
 
@@ -34,7 +34,7 @@ fn day3_part2_example() {
 fn day3_part2_actual() {
     let f = File::open("data/day3_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let rucksack_vector = read_rucksack_vector(&mut reader);
-    let sum = badge_priority_sum(&rucksack_vector);
+    let rucksack_vector = read_rucksack_vector(&mut reader).unwrap();
+    let sum = badge_priority_sum(&rucksack_vector).unwrap();
     assert_eq!(2780, sum);
 }
@@ -7,7 +7,7 @@ use std::fs::File;
 fn day4_part1_example() {
     let f = File::open("data/day4_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let sp = read_sequence_pairs(&mut reader);
+    let sp = read_sequence_pairs(&mut reader).unwrap();
     let count = count_contained_pairs(&sp);
     assert_eq!(2, count);
 }
@@ -16,7 +16,7 @@ fn day4_part1_example() {
 fn day4_part1_actual() {
     let f = File::open("data/day4_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let sp = read_sequence_pairs(&mut reader);
+    let sp = read_sequence_pairs(&mut reader).unwrap();
     let count = count_contained_pairs(&sp);
     assert_eq!(582, count);
 }
@@ -25,7 +25,7 @@ fn day4_part1_actual() {
 fn day4_part2_example() {
     let f = File::open("data/day4_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let sp = read_sequence_pairs(&mut reader);
+    let sp = read_sequence_pairs(&mut reader).unwrap();
     let count = count_overlapped_pairs(&sp);
     assert_eq!(4, count);
 }
@@ -34,7 +34,7 @@ fn day4_part2_example() {
 fn day4_part2_actual() {
     let f = File::open("data/day4_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let sp = read_sequence_pairs(&mut reader);
+    let sp = read_sequence_pairs(&mut reader).unwrap();
     let count = count_overlapped_pairs(&sp);
     assert_eq!(893, count);
 }
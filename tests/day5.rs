@@ -7,7 +7,7 @@ use std::fs::File;
 fn day5_part1_example() {
     let f = File::open("data/day5_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader);
+    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader).unwrap();
     process_moves(&mut stacks, &moves);
     let top = top_crate_str(&stacks);
     assert_eq!("CMZ", top);
@@ -17,7 +17,7 @@ fn day5_part1_example() {
 fn day5_part1_actual() {
     let f = File::open("data/day5_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader);
+    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader).unwrap();
     process_moves(&mut stacks, &moves);
     let top = top_crate_str(&stacks);
     assert_eq!("WHTLRMZRC", top);
@@ -27,7 +27,7 @@ fn day5_part1_actual() {
 fn day5_part2_example() {
     let f = File::open("data/day5_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader);
+    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader).unwrap();
     process_moves2(&mut stacks, &moves);
     let top = top_crate_str(&stacks);
     assert_eq!("MCD", top);
@@ -37,7 +37,7 @@ fn day5_part2_example() {
 fn day5_part2_actual() {
     let f = File::open("data/day5_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader);
+    let (mut stacks, moves) = read_stacks_and_procedure(&mut reader).unwrap();
     process_moves2(&mut stacks, &moves);
     let top = top_crate_str(&stacks);
     assert_eq!("GMPMLWNMG", top);
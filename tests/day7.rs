@@ -7,9 +7,9 @@ use std::fs::File;
 fn day7_part1_example() {
     let f = File::open("data/day7_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let fs = read_fs_tree(&mut reader);
-    let _sz = update_sizes(fs.clone());
-    let sum = sum_dirs_le(fs.clone(), 100000);
+    let mut fs = read_fs_tree(&mut reader).unwrap();
+    let _sz = update_sizes(&mut fs);
+    let sum = sum_dirs_le(&fs, 100000);
     assert_eq!(95437, sum);
 }
 
@@ -17,9 +17,9 @@ fn day7_part1_example() {
 fn day7_part1_actual() {
     let f = File::open("data/day7_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let fs = read_fs_tree(&mut reader);
-    let _sz = update_sizes(fs.clone());
-    let sum = sum_dirs_le(fs.clone(), 100000);
+    let mut fs = read_fs_tree(&mut reader).unwrap();
+    let _sz = update_sizes(&mut fs);
+    let sum = sum_dirs_le(&fs, 100000);
     assert_eq!(919137, sum);
 }
 
@@ -27,34 +27,34 @@ fn day7_part1_actual() {
 fn day7_part2_example() {
     let f = File::open("data/day7_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let fs = read_fs_tree(&mut reader);
-    let _sz = update_sizes(fs.clone());
+    let mut fs = read_fs_tree(&mut reader).unwrap();
+    let _sz = update_sizes(&mut fs);
 
     let total_available_space = 70000000;
-    let total_used_space = fs.borrow().size;
+    let total_used_space = fs.node(fs.root()).size;
     let total_unused_space = total_available_space - total_used_space;
 
     let min_space_required = 30000000;
     let smallest_dir_size_to_delete = min_space_required - total_unused_space;
 
-    let sdgt = smallest_dir_gt(fs.clone(), smallest_dir_size_to_delete);
-    assert_eq!(24933642, sdgt);
+    let sdgt = smallest_dir_gt(&fs, smallest_dir_size_to_delete);
+    assert_eq!(Some(24933642), sdgt);
 }
 
 #[test]
 fn day7_part2_actual() {
     let f = File::open("data/day7_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let fs = read_fs_tree(&mut reader);
-    let _sz = update_sizes(fs.clone());
+    let mut fs = read_fs_tree(&mut reader).unwrap();
+    let _sz = update_sizes(&mut fs);
 
     let total_available_space = 70000000;
-    let total_used_space = fs.borrow().size;
+    let total_used_space = fs.node(fs.root()).size;
     let total_unused_space = total_available_space - total_used_space;
 
     let min_space_required = 30000000;
     let smallest_dir_size_to_delete = min_space_required - total_unused_space;
 
-    let sdgt = smallest_dir_gt(fs.clone(), smallest_dir_size_to_delete);
-    assert_eq!(2877389, sdgt);
+    let sdgt = smallest_dir_gt(&fs, smallest_dir_size_to_delete);
+    assert_eq!(Some(2877389), sdgt);
 }
@@ -7,7 +7,7 @@ use std::fs::File;
 fn day8_part1_example() {
     let f = File::open("data/day8_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let trees = read_tree_heights(&mut reader);
+    let trees = read_tree_heights(&mut reader).unwrap();
     let visible_trees = count_visible_trees(&trees);
     assert_eq!(21, visible_trees);
 }
@@ -16,7 +16,7 @@ fn day8_part1_example() {
 fn day8_part1_actual() {
     let f = File::open("data/day8_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let trees = read_tree_heights(&mut reader);
+    let trees = read_tree_heights(&mut reader).unwrap();
     let visible_trees = count_visible_trees(&trees);
     assert_eq!(1789, visible_trees);
 }
@@ -25,7 +25,7 @@ fn day8_part1_actual() {
 fn day8_part2_example() {
     let f = File::open("data/day8_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let trees = read_tree_heights(&mut reader);
+    let trees = read_tree_heights(&mut reader).unwrap();
     let highest_score = highest_scenic_score(&trees);
     assert_eq!(8, highest_score);
 }
@@ -34,7 +34,7 @@ fn day8_part2_example() {
 fn day8_part2_actual() {
     let f = File::open("data/day8_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let trees = read_tree_heights(&mut reader);
+    let trees = read_tree_heights(&mut reader).unwrap();
     let highest_score = highest_scenic_score(&trees);
     assert_eq!(314820, highest_score);
 }
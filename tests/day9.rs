@@ -8,7 +8,7 @@ use std::fs::File;
 fn day9_part1_example() {
     let f = File::open("data/day9_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let movements = read_movements(&mut reader);
+    let movements = read_movements(&mut reader).unwrap();
 
     let mut rope: Rope = Rope::new(2);
 
@@ -23,7 +23,7 @@ fn day9_part1_example() {
 fn day9_part1_actual() {
     let f = File::open("data/day9_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let movements = read_movements(&mut reader);
+    let movements = read_movements(&mut reader).unwrap();
 
     let mut rope: Rope = Rope::new(2);
 
@@ -38,7 +38,7 @@ fn day9_part1_actual() {
 fn day9_part2_example() {
     let f = File::open("data/day9_example.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let movements = read_movements(&mut reader);
+    let movements = read_movements(&mut reader).unwrap();
 
     let mut rope: Rope = Rope::new(10);
 
@@ -53,7 +53,7 @@ fn day9_part2_example() {
 fn day9_part2_actual() {
     let f = File::open("data/day9_actual.txt").unwrap();
     let mut reader = BufReader::new(f);
-    let movements = read_movements(&mut reader);
+    let movements = read_movements(&mut reader).unwrap();
 
     let mut rope: Rope = Rope::new(10);
 
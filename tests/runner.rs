@@ -0,0 +1,52 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const AOC: &str = env!("CARGO_BIN_EXE_aoc");
+
+#[test]
+fn runner_single_part() {
+    let output = Command::new(AOC)
+        .args(["--day", "1", "--part", "2", "data/day1_example.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!("45000\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn runner_both_parts() {
+    let output = Command::new(AOC)
+        .args(["--day", "4", "--input", "data/day4_example.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!("Part 1: 2\nPart 2: 4\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn runner_stdin() {
+    let mut child = Command::new(AOC)
+        .args(["--day", "2", "--part", "1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"A Y\nB X\nC Z\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    assert_eq!("15\n", String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn runner_errors() {
+    let output = Command::new(AOC).args(["--day", "1", "--part", "7"]).output().unwrap();
+    assert_eq!(Some(2), output.status.code());
+
+    let output = Command::new(AOC).args(["--day", "42", "data/day1_example.txt"]).output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no solution for day 42"));
+
+    let output = Command::new(AOC).args(["--day", "9", "data/day1_example.txt"]).output().unwrap();
+    assert_eq!(Some(1), output.status.code());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("day 9 line 1"));
+}
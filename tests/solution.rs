@@ -0,0 +1,91 @@
+use advent_of_code_2022::solution::*;
+
+use std::io::BufReader;
+use std::fs::File;
+
+fn solve_example(day: u32) -> (String, String) {
+    let f = File::open(format!("data/day{day}_example.txt")).unwrap();
+    let mut reader = BufReader::new(f);
+    let solution = parse(day, &mut reader).unwrap().unwrap();
+    (solution.part1().to_string(), solution.part2().to_string())
+}
+
+#[test]
+fn registry_days() {
+    assert_eq!((1..=11).collect::<Vec<u32>>(), days());
+    assert!(parser(12).is_none());
+
+    let mut buf = "".as_bytes();
+    assert!(parse(0, &mut buf).is_none());
+}
+
+#[test]
+fn examples() {
+    assert_eq!(("24000".to_string(), "45000".to_string()), solve_example(1));
+    assert_eq!(("15".to_string(), "12".to_string()), solve_example(2));
+    assert_eq!(("157".to_string(), "70".to_string()), solve_example(3));
+    assert_eq!(("2".to_string(), "4".to_string()), solve_example(4));
+    assert_eq!(("CMZ".to_string(), "MCD".to_string()), solve_example(5));
+    assert_eq!(("95437".to_string(), "24933642".to_string()), solve_example(7));
+    assert_eq!(("21".to_string(), "8".to_string()), solve_example(8));
+    assert_eq!(("13".to_string(), "1".to_string()), solve_example(9));
+    assert_eq!("13140", solve_example(10).0);
+    assert_eq!(("10605".to_string(), "2713310158".to_string()), solve_example(11));
+}
+
+#[test]
+fn empty_input() {
+    let mut buf = "".as_bytes();
+    let solution = parse(1, &mut buf).unwrap().unwrap();
+    assert_eq!("0", solution.part1().to_string());
+    assert_eq!("0", solution.part2().to_string());
+}
+
+#[test]
+fn day7_nothing_to_delete() {
+    let mut buf = "$ cd /\n$ ls\n10 a".as_bytes();
+    let solution = parse(7, &mut buf).unwrap().unwrap();
+    assert_eq!("10", solution.part1().to_string());
+    assert_eq!("nothing to delete", solution.part2().to_string());
+}
+
+#[test]
+fn day3_incomplete_group() {
+    let mut buf = "vJrwpWtwJgWrhcsFMMfFFhFp\njqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL".as_bytes();
+    let solution = parse(3, &mut buf).unwrap().unwrap();
+    assert_eq!("54", solution.part1().to_string());
+    assert_eq!("2 rucksacks can't be split into groups of three", solution.part2().to_string());
+}
+
+#[test]
+fn day6_example() {
+    let mut buf = "mjqjpqmgbljsphdztnvjfqwrcgsmlb".as_bytes();
+    let solution = parse(6, &mut buf).unwrap().unwrap();
+    assert_eq!("7", solution.part1().to_string());
+    assert_eq!("19", solution.part2().to_string());
+}
+
+#[test]
+fn day10_example_image() {
+    let (_, image) = solve_example(10);
+    let lines = image.lines().filter(|l| !l.is_empty()).collect::<Vec<&str>>();
+    assert_eq!(6, lines.len());
+    assert_eq!("##..##..##..##..##..##..##..##..##..##..", lines[0]);
+    assert_eq!("#######.......#######.......#######.....", lines[5]);
+}
+
+#[test]
+fn day10_actual_letters() {
+    let f = File::open("data/day10_actual.txt").unwrap();
+    let mut reader = BufReader::new(f);
+    let solution = parse(10, &mut reader).unwrap().unwrap();
+    assert_eq!("RJERPEFC", solution.part2().to_string());
+}
+
+#[test]
+fn parse_error() {
+    let mut buf = "1000\nabc".as_bytes();
+    let err = parse(1, &mut buf).unwrap().err().unwrap();
+    assert_eq!(1, err.day);
+    assert_eq!(2, err.line);
+}